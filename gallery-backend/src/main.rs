@@ -13,11 +13,17 @@ mod workflow;
 use crate::process::initialization::initialize;
 use crate::public::constant::runtime::{INDEX_RUNTIME, ROCKET_RUNTIME};
 use crate::public::error_data::handle_error;
+use crate::public::storage::STORAGE;
 use crate::public::tui::{DASHBOARD, tui_task};
 use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::reconcile::ReconcileTask;
+use crate::tasks::batcher::resume_job::ResumeJobTask;
 use crate::tasks::batcher::start_watcher::StartWatcherTask;
 use crate::tasks::batcher::update_tree::UpdateTreeTask;
-use crate::tasks::looper::start_expire_check_loop;
+use crate::tasks::looper::{
+    start_event_cluster_loop, start_expire_check_loop, start_gc_loop, start_share_prune_loop,
+    start_trash_purge_loop,
+};
 
 use public::constant::redb::{ALBUM_TABLE, DATA_TABLE};
 use public::db::tree::TREE;
@@ -29,6 +35,7 @@ use router::{
     delete::generate_delete_routes, get::generate_get_routes, post::generate_post_routes,
     put::generate_put_routes,
 };
+use std::sync::LazyLock;
 use std::thread;
 use std::time::Instant;
 
@@ -47,6 +54,8 @@ async fn build_rocket() -> rocket::Rocket<rocket::Build> {
 }
 
 fn main() -> Result<()> {
+    // The S3 backend holds a blocking HTTP client, which must not be built inside a runtime.
+    LazyLock::force(&STORAGE);
     let worker_handle = thread::spawn(|| {
         INDEX_RUNTIME.block_on(async {
             let rx = initialize();
@@ -61,7 +70,13 @@ fn main() -> Result<()> {
             txn.commit().unwrap();
             BATCH_COORDINATOR.execute_batch_detached(StartWatcherTask);
             BATCH_COORDINATOR.execute_batch_detached(UpdateTreeTask);
+            BATCH_COORDINATOR.execute_batch_detached(ResumeJobTask::on_startup());
+            BATCH_COORDINATOR.execute_batch_detached(ReconcileTask::all());
             start_expire_check_loop();
+            start_gc_loop();
+            start_trash_purge_loop();
+            start_event_cluster_loop();
+            start_share_prune_loop();
 
             if let Some(sc) = superconsole::SuperConsole::new() {
                 INDEX_RUNTIME.spawn(async move {
@@ -2,9 +2,9 @@ use anyhow::{Context, Result};
 use arrayvec::ArrayString;
 use blake3::Hasher;
 use rand::{Rng, distr::Alphanumeric};
-use std::{fs::File, io::Read};
+use std::io::Read;
 
-pub fn blake3_hasher(mut file: File) -> Result<ArrayString<64>> {
+pub fn blake3_hasher(mut file: impl Read) -> Result<ArrayString<64>> {
     let mut hasher = Hasher::new(); // :contentReference[oaicite:5]{index=5}
     let mut buffer = [0u8; 512 * 1024];
 
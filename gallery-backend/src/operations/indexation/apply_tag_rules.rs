@@ -0,0 +1,68 @@
+use crate::operations::open_db::open_album_table;
+use crate::public::config::{AutoTagRule, SERVER_CONFIG};
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::database_struct::database::definition::Database;
+use arrayvec::ArrayString;
+use std::collections::HashSet;
+
+type Filter = Box<dyn Fn(&AbstractData) -> bool + Sync + Send>;
+
+/// The `autoTag` rules, together with which of their albums exist; load once per pass.
+///
+/// Filters such as `AlbumTree` capture the album tree as it is when loaded, so a `TagRules`
+/// must not outlive the pass it was loaded for.
+pub struct TagRules {
+    filters: Vec<(&'static AutoTagRule, Filter)>,
+    existing_albums: HashSet<ArrayString<64>>,
+}
+
+impl TagRules {
+    pub fn load() -> Self {
+        let filters = SERVER_CONFIG
+            .auto_tag
+            .rules
+            .iter()
+            .map(|rule| (rule, rule.when.clone().generate_filter()))
+            .collect();
+        let album_table = open_album_table();
+        let existing_albums = SERVER_CONFIG
+            .auto_tag
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.albums)
+            .filter(|album_id| album_table.get(&***album_id).ok().flatten().is_some())
+            .copied()
+            .collect();
+        Self {
+            filters,
+            existing_albums,
+        }
+    }
+
+    /// Add the tags and albums of every rule the item matches; returns whether anything changed.
+    pub fn apply(&self, database: &mut Database) -> bool {
+        if self.filters.is_empty() {
+            return false;
+        }
+        let abstract_data = AbstractData::Database(database.clone());
+        let matched: Vec<&AutoTagRule> = self
+            .filters
+            .iter()
+            .filter(|(_, filter)| filter(&abstract_data))
+            .map(|(rule, _)| *rule)
+            .collect();
+
+        let mut changed = false;
+        for rule in matched {
+            for tag in &rule.tags {
+                changed |= database.tag.insert(tag.clone());
+            }
+            for album_id in &rule.albums {
+                if self.existing_albums.contains(album_id) {
+                    changed |= database.album.insert(*album_id);
+                }
+            }
+        }
+        changed
+    }
+}
@@ -2,7 +2,10 @@ use super::video_ffprobe::video_duration;
 use crate::{
     operations::indexation::generate_ffmpeg::create_silent_ffmpeg_command,
     process::info::process_image_info,
-    public::{structure::database_struct::database::definition::Database, tui::DASHBOARD},
+    public::{
+        storage::commit_staged_file, structure::database_struct::database::definition::Database,
+        tui::DASHBOARD,
+    },
 };
 use anyhow::Context;
 use anyhow::Result;
@@ -20,13 +23,13 @@ static REGEX_OUT_TIME_US: LazyLock<Regex> =
 
 /// Compresses a video file, reporting progress by parsing ffmpeg's output.
 pub fn generate_compressed_video(database: &mut Database) -> Result<()> {
-    let duration_result = video_duration(&database.imported_path_string());
+    let duration_result = video_duration(&database.original_path_string());
     let duration = match duration_result {
         // Handle static GIFs by delegating to the image processor.
         Ok(d) if (d * 1000.0) as u32 == 100 => {
             info!(
                 "Static GIF detected. Processing as image: {:?}",
-                database.imported_path_string()
+                database.original_path_string()
             );
             database.ext_type = "image".to_string();
             return process_image_info(database);
@@ -38,7 +41,7 @@ pub fn generate_compressed_video(database: &mut Database) -> Result<()> {
         {
             info!(
                 "Potentially corrupt or non-standard GIF. Processing as image: {:?}",
-                database.imported_path_string()
+                database.original_path_string()
             );
             database.ext_type = "image".to_string();
             return process_image_info(database);
@@ -47,7 +50,7 @@ pub fn generate_compressed_video(database: &mut Database) -> Result<()> {
         Err(err) => {
             return Err(anyhow::anyhow!(
                 "Failed to get video duration for {:?}: {}",
-                database.imported_path_string(),
+                database.original_path_string(),
                 err
             ));
         }
@@ -57,7 +60,7 @@ pub fn generate_compressed_video(database: &mut Database) -> Result<()> {
     cmd.args([
         "-y", // Overwrite output file if it exists
         "-i",
-        &database.imported_path_string(),
+        &database.original_path_string(),
         "-vf",
         // Scale video to a max height of 720p, ensuring dimensions are even.
         &format!(
@@ -101,5 +104,8 @@ pub fn generate_compressed_video(database: &mut Database) -> Result<()> {
     child
         .wait()
         .context("Failed to wait for ffmpeg child process")?;
+
+    commit_staged_file(&database.compressed_key(), &database.compressed_path())
+        .context("Failed to store compressed video")?;
     Ok(())
 }
@@ -1,38 +1,38 @@
-use crate::public::structure::database_struct::database::definition::Database;
+use crate::public::{storage::STORAGE, structure::database_struct::database::definition::Database};
 use anyhow::{Context, Result, bail};
 use image::DynamicImage;
 use std::fs::read;
-use std::path::PathBuf;
 
 /// Generate a `DynamicImage` either from the original image or
 /// from its thumbnail, adding *context* at every fallible step.
 pub fn generate_dynamic_image(database: &Database) -> Result<DynamicImage> {
-    let img_path = if database.ext_type == "image" {
-        database.imported_path()
+    let file_in_memory = if database.ext_type == "image" {
+        let img_path = database.original_path();
+        read(&img_path).context(format!("failed to read file into memory: {:?}", img_path))?
     } else {
-        PathBuf::from(database.thumbnail_path())
+        STORAGE.get(&database.thumbnail_key()).context(format!(
+            "failed to read thumbnail into memory: {}",
+            database.thumbnail_key()
+        ))?
     };
 
-    let dynamic_image =
-        decode_image(&img_path).context(format!("failed to decode image: {:?}", img_path))?;
+    let dynamic_image = decode_image(&file_in_memory)
+        .context(format!("failed to decode image: {}", database.hash))?;
 
     Ok(dynamic_image)
 }
 
-fn decode_image(file_path: &PathBuf) -> Result<DynamicImage> {
-    let file_in_memory =
-        read(file_path).context(format!("failed to read file into memory: {:?}", file_path))?;
-
+fn decode_image(file_in_memory: &Vec<u8>) -> Result<DynamicImage> {
     let decoders: Vec<fn(&Vec<u8>) -> Result<DynamicImage>> = vec![image_crate_decoder];
 
     for decoder in decoders {
-        match decoder(&file_in_memory) {
+        match decoder(file_in_memory) {
             Ok(decoded_image) => return Ok(decoded_image),
             Err(_) => continue,
         }
     }
 
-    bail!("all decoders failed for file: {:?}", file_path);
+    bail!("all decoders failed");
 }
 
 fn image_crate_decoder(file_in_memory: &Vec<u8>) -> Result<DynamicImage> {
@@ -0,0 +1,221 @@
+use crate::operations::indexation::generate_xmp::XmpMetadata;
+
+const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+const RESOURCE_IPTC: u16 = 0x0404;
+
+const DATASET_CODED_CHARACTER_SET: u8 = 90;
+/// `ESC % G`, the ISO 2022 escape sequence for UTF-8.
+const CHARSET_UTF8: &[u8] = b"\x1B%G";
+
+const DATASET_OBJECT_NAME: u8 = 5;
+const DATASET_KEYWORDS: u8 = 25;
+const DATASET_CAPTION: u8 = 120;
+
+/// How the text datasets of record 2 are encoded, as declared by dataset 1:90.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Utf8,
+    Latin1,
+    /// No 1:90: UTF-8 when the bytes are valid UTF-8, otherwise Latin-1.
+    Undeclared,
+}
+
+impl Charset {
+    fn decode(self, value: &[u8]) -> String {
+        match (self, std::str::from_utf8(value)) {
+            (Charset::Utf8, _) => String::from_utf8_lossy(value).into_owned(),
+            (Charset::Undeclared, Ok(text)) => text.to_string(),
+            // Latin-1 bytes are the first 256 code points
+            (Charset::Latin1 | Charset::Undeclared, _) => {
+                value.iter().map(|&byte| byte as char).collect()
+            }
+        }
+    }
+}
+
+/// Read IPTC-IIM keywords, caption and object name from a JPEG's Photoshop APP13 segment,
+/// mapped onto the same fields as their XMP counterparts.
+pub fn read_iptc(bytes: &[u8]) -> Option<XmpMetadata> {
+    let iim = find_iptc_resource(find_app13(bytes)?)?;
+
+    let mut iptc = XmpMetadata::default();
+    let mut charset = Charset::Undeclared;
+    let mut offset = 0;
+    while offset + 5 <= iim.len() && iim[offset] == 0x1C {
+        let record = iim[offset + 1];
+        let dataset = iim[offset + 2];
+        let size = u16::from_be_bytes([iim[offset + 3], iim[offset + 4]]) as usize;
+        // Extended datasets (high bit set) are never used for text fields
+        if size & 0x8000 != 0 {
+            break;
+        }
+        let start = offset + 5;
+        // A truncated dataset ends the block; keep what was read before it
+        let Some(value) = iim.get(start..start + size) else {
+            break;
+        };
+        offset = start + size;
+
+        if record == 1 && dataset == DATASET_CODED_CHARACTER_SET {
+            charset = if value == CHARSET_UTF8 {
+                Charset::Utf8
+            } else {
+                Charset::Latin1
+            };
+            continue;
+        }
+        if record != 2 {
+            continue;
+        }
+        let text = charset.decode(value).trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        match dataset {
+            DATASET_KEYWORDS => iptc.subject.push(text),
+            DATASET_CAPTION => iptc.description = Some(text),
+            DATASET_OBJECT_NAME => iptc.title = Some(text),
+            _ => {}
+        }
+    }
+    Some(iptc)
+}
+
+/// Payload of the first APP13 segment carrying Photoshop resources.
+fn find_app13(bytes: &[u8]) -> Option<&[u8]> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut offset = 2;
+    while offset + 4 <= bytes.len() && bytes[offset] == 0xFF {
+        let marker = bytes[offset + 1];
+        // Start of scan: no more metadata segments follow
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]) as usize;
+        let payload = bytes.get(offset + 4..offset + 2 + length)?;
+        if marker == 0xED && payload.starts_with(PHOTOSHOP_SIGNATURE) {
+            return Some(&payload[PHOTOSHOP_SIGNATURE.len()..]);
+        }
+        offset += 2 + length;
+    }
+    None
+}
+
+/// The IPTC-NAA block among the `8BIM` image resources.
+fn find_iptc_resource(resources: &[u8]) -> Option<&[u8]> {
+    let mut offset = 0;
+    while offset + 6 <= resources.len() && &resources[offset..offset + 4] == b"8BIM" {
+        let id = u16::from_be_bytes([resources[offset + 4], resources[offset + 5]]);
+        // Pascal-string name, padded so that length byte plus name is even
+        let name_length = *resources.get(offset + 6)? as usize;
+        let mut cursor = offset + 6 + name_length + 1;
+        cursor += cursor % 2;
+        let size = u32::from_be_bytes(resources.get(cursor..cursor + 4)?.try_into().ok()?) as usize;
+        cursor += 4;
+        let data = resources.get(cursor..cursor + size)?;
+        if id == RESOURCE_IPTC {
+            return Some(data);
+        }
+        offset = cursor + size + size % 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(record: u8, dataset: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x1C, record, dataset];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    /// A JPEG with an APP1 segment, then an APP13 segment holding `resources`.
+    fn jpeg(resources: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut payload = PHOTOSHOP_SIGNATURE.to_vec();
+        for (id, data) in resources {
+            payload.extend_from_slice(b"8BIM");
+            payload.extend_from_slice(&id.to_be_bytes());
+            // Empty Pascal name, padded to even length
+            payload.extend_from_slice(&[0, 0]);
+            payload.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            payload.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                payload.push(0);
+            }
+        }
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x04, b'x', b'y'];
+        bytes.extend_from_slice(&[0xFF, 0xED]);
+        bytes.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+        bytes
+    }
+
+    #[test]
+    fn reads_text_datasets() {
+        let iim = [
+            dataset(1, DATASET_CODED_CHARACTER_SET, CHARSET_UTF8),
+            dataset(2, DATASET_KEYWORDS, "café".as_bytes()),
+            dataset(2, DATASET_KEYWORDS, b" beach "),
+            dataset(2, DATASET_KEYWORDS, b""),
+            dataset(2, DATASET_CAPTION, b"Caption"),
+            dataset(2, DATASET_OBJECT_NAME, b"Title"),
+        ]
+        .concat();
+        // A resource before the IPTC block, with an odd size that needs padding
+        let iptc = read_iptc(&jpeg(&[(0x03ED, b"odd"), (RESOURCE_IPTC, &iim)])).unwrap();
+        assert_eq!(iptc.subject, ["café", "beach"]);
+        assert_eq!(iptc.description.as_deref(), Some("Caption"));
+        assert_eq!(iptc.title.as_deref(), Some("Title"));
+    }
+
+    #[test]
+    fn honors_declared_latin1() {
+        // 0xE9 is é in Latin-1; `ESC - A` selects ISO 8859-1
+        let iim = [
+            dataset(1, DATASET_CODED_CHARACTER_SET, b"\x1B-A"),
+            dataset(2, DATASET_KEYWORDS, b"caf\xE9"),
+            // Valid UTF-8, still read as Latin-1
+            dataset(2, DATASET_CAPTION, b"\xC3\xA9"),
+        ]
+        .concat();
+        let iptc = read_iptc(&jpeg(&[(RESOURCE_IPTC, &iim)])).unwrap();
+        assert_eq!(iptc.subject, ["café"]);
+        assert_eq!(iptc.description.as_deref(), Some("Ã©"));
+    }
+
+    #[test]
+    fn undeclared_charset_falls_back_to_latin1() {
+        let iim = [
+            dataset(2, DATASET_KEYWORDS, "café".as_bytes()),
+            dataset(2, DATASET_KEYWORDS, b"caf\xE9"),
+        ]
+        .concat();
+        let iptc = read_iptc(&jpeg(&[(RESOURCE_IPTC, &iim)])).unwrap();
+        assert_eq!(iptc.subject, ["café", "café"]);
+    }
+
+    #[test]
+    fn rejects_missing_blocks() {
+        assert_eq!(read_iptc(b"not a jpeg"), None);
+        assert_eq!(read_iptc(&jpeg(&[(0x03ED, b"no iptc")])), None);
+    }
+
+    #[test]
+    fn keeps_datasets_before_a_truncated_one() {
+        let mut iim = [
+            dataset(2, DATASET_KEYWORDS, b"beach"),
+            dataset(2, DATASET_CAPTION, b"Caption"),
+        ]
+        .concat();
+        iim.truncate(iim.len() - 3);
+        let iptc = read_iptc(&jpeg(&[(RESOURCE_IPTC, &iim)])).unwrap();
+        assert_eq!(iptc.subject, ["beach"]);
+        assert_eq!(iptc.description, None);
+    }
+}
@@ -3,11 +3,14 @@ use crate::{
         indexation::generate_ffmpeg::create_silent_ffmpeg_command,
         utils::resize::small_width_height,
     },
-    public::structure::database_struct::database::definition::Database,
+    public::{
+        storage::{STORAGE, commit_staged_file},
+        structure::database_struct::database::definition::Database,
+    },
 };
 use anyhow::{Context, Result, anyhow};
 use image::{DynamicImage, ImageFormat};
-use std::process::Stdio;
+use std::{io::Cursor, path::Path, process::Stdio};
 
 /// Generate a JPEG thumbnail for an **image** asset, propagating
 /// every error with clear human‑readable context strings.
@@ -22,25 +25,18 @@ pub fn generate_thumbnail_for_image(
         .thumbnail_exact(compressed_width, compressed_height)
         .to_rgb8();
 
-    // Resolve parent directory of the compressed path
-    let binding = database.compressed_path();
-    let parent_path = binding.parent().ok_or_else(|| {
-        anyhow!(
-            "failed to determine parent directory of {:?}",
-            database.compressed_path()
-        )
-    })?;
-
-    // Ensure the directory exists
-    std::fs::create_dir_all(parent_path)
-        .context(format!("failed to create directory tree {:?}", parent_path))?;
-
-    // Persist the thumbnail as JPEG
+    // Encode the thumbnail as JPEG
+    let mut jpeg_bytes = Vec::new();
     thumbnail_image
-        .save_with_format(database.compressed_path(), ImageFormat::Jpeg)
+        .write_to(&mut Cursor::new(&mut jpeg_bytes), ImageFormat::Jpeg)
+        .context("failed to encode JPEG thumbnail")?;
+
+    // Persist it through the storage backend
+    STORAGE
+        .put(&database.compressed_key(), &jpeg_bytes)
         .context(format!(
-            "failed to save JPEG thumbnail to {:?}",
-            database.compressed_path()
+            "failed to save JPEG thumbnail to {}",
+            database.compressed_key()
         ))?;
 
     Ok(())
@@ -63,7 +59,7 @@ pub fn generate_thumbnail_for_video(database: &Database) -> Result<()> {
     cmd.args([
         "-y",
         "-i",
-        &database.imported_path_string(),
+        &database.original_path_string(),
         "-ss",
         "0",
         "-vframes",
@@ -87,5 +83,8 @@ pub fn generate_thumbnail_for_video(database: &Database) -> Result<()> {
         ));
     }
 
+    commit_staged_file(&database.thumbnail_key(), Path::new(&thumbnail_path))
+        .context("failed to store video thumbnail")?;
+
     Ok(())
 }
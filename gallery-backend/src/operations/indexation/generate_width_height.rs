@@ -12,7 +12,7 @@ pub fn generate_image_width_height(dynamic_image: &DynamicImage) -> (u32, u32) {
 /// Probe a video file using `ffprobe` (through `video_width_height`) to
 /// obtain `(width, height)`, adding explicit context to every `?` site.
 pub fn generate_video_width_height(database: &Database) -> Result<(u32, u32)> {
-    let imported = database.imported_path_string();
+    let imported = database.original_path_string();
 
     let width = video_width_height("width", &imported)
         .context(format!("failed to obtain video width for {:?}", imported))?;
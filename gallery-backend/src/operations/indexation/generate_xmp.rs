@@ -0,0 +1,363 @@
+use crate::operations::indexation::generate_iptc::read_iptc;
+use crate::public::config::SERVER_CONFIG;
+use crate::public::structure::database_struct::database::definition::Database;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::UNIX_EPOCH,
+};
+
+/// The parts of an XMP packet Urocissa understands; IPTC fields are mapped onto the same names.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmpMetadata {
+    /// `xmp:Rating`; `None` when absent. Rejected (`-1`) counts as unrated.
+    pub rating: Option<u8>,
+    /// `xmp:Label`, e.g. `Red`.
+    pub label: Option<String>,
+    /// `dc:subject` keywords.
+    pub subject: Vec<String>,
+    /// `dc:description`, the caption.
+    pub description: Option<String>,
+    /// `dc:title`.
+    pub title: Option<String>,
+    /// Modification time in milliseconds of the newest file the values were read from.
+    pub modified: Option<u128>,
+}
+
+impl XmpMetadata {
+    /// Take every field `other` has, keeping ours where it has none.
+    fn overlay(&mut self, other: XmpMetadata) {
+        self.rating = other.rating.or(self.rating);
+        self.label = other.label.or(self.label.take());
+        if !other.subject.is_empty() {
+            self.subject = other.subject;
+        }
+        self.description = other.description.or(self.description.take());
+        self.title = other.title.or(self.title.take());
+        self.modified = self.modified.max(other.modified);
+    }
+}
+
+static RE_XMP_PACKET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<x:xmpmeta.*?</x:xmpmeta>").expect("regex compilation failure")
+});
+static RE_RATING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"xmp:Rating\s*=\s*["'](-?\d+)["']|<xmp:Rating>\s*(-?\d+)\s*</xmp:Rating>"#)
+        .expect("regex compilation failure")
+});
+static RE_LABEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"xmp:Label\s*=\s*"([^"]*)"|xmp:Label\s*=\s*'([^']*)'|<xmp:Label>([^<]*)</xmp:Label>"#,
+    )
+    .expect("regex compilation failure")
+});
+static RE_SUBJECT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:subject>\s*<rdf:Bag>(.*?)</rdf:Bag>").expect("regex compilation failure")
+});
+static RE_DESCRIPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:description>\s*<rdf:Alt>\s*<rdf:li[^>]*>(.*?)</rdf:li>")
+        .expect("regex compilation failure")
+});
+static RE_TITLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:title>\s*<rdf:Alt>\s*<rdf:li[^>]*>(.*?)</rdf:li>")
+        .expect("regex compilation failure")
+});
+static RE_LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<rdf:li[^>]*>(.*?)</rdf:li>").expect("regex compilation failure")
+});
+
+/// Read metadata for an item: IPTC embedded in the original, then its embedded XMP packet, then
+/// a sidecar next to the source file, which is what darktable and Lightroom keep up to date.
+/// Each later source overrides the earlier ones field by field.
+pub fn generate_xmp(database: &Database) -> XmpMetadata {
+    let mut xmp = XmpMetadata::default();
+    if database.ext_type == "image"
+        && let Some(path) = database.original_path_opt()
+        && let Ok(bytes) = fs::read(&path)
+    {
+        let modified = modified_millis(&path);
+        if let Some(iptc) = read_iptc(&bytes) {
+            xmp.overlay(XmpMetadata { modified, ..iptc });
+        }
+        if let Some(embedded) = read_packet(&bytes) {
+            xmp.overlay(XmpMetadata {
+                modified,
+                ..parse_xmp(&embedded)
+            });
+        }
+    }
+    if let Some(sidecar) = database
+        .live_source_path_opt()
+        .and_then(|source| existing_sidecar_path(&source))
+        && let Ok(text) = fs::read_to_string(&sidecar)
+    {
+        xmp.overlay(XmpMetadata {
+            modified: modified_millis(&sidecar),
+            ..parse_xmp(&text)
+        });
+    }
+    xmp
+}
+
+fn modified_millis(path: &Path) -> Option<u128> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis())
+}
+
+/// Copy ratings, labels, keywords and the caption into the item; keywords become tags under
+/// `metadata.keywordTagPrefix`.
+///
+/// A rating, label or caption edited in Urocissa is only replaced by files modified after the
+/// edit; unset fields are always filled in.
+pub fn apply_xmp(database: &mut Database, xmp: XmpMetadata) {
+    let newer_than_edit = match (database.metadata_edited_at, xmp.modified) {
+        (None, _) => true,
+        (Some(edited_at), Some(modified)) => modified > edited_at,
+        (Some(_), None) => false,
+    };
+    if let Some(rating) = xmp.rating
+        && (newer_than_edit || database.rating == 0)
+    {
+        database.rating = rating;
+    }
+    if xmp.label.is_some() && (newer_than_edit || database.label.is_none()) {
+        database.label = xmp.label;
+    }
+    database.tag.extend(
+        xmp.subject
+            .into_iter()
+            .map(|keyword| keyword_to_tag(&keyword)),
+    );
+    if let Some(description) = xmp.description.or(xmp.title)
+        && (newer_than_edit || database.description.is_none())
+    {
+        database.description = Some(description);
+    }
+}
+
+pub fn keyword_to_tag(keyword: &str) -> String {
+    prefix_keyword(keyword, &SERVER_CONFIG.metadata.keyword_tag_prefix)
+}
+
+/// Inverse of [`keyword_to_tag`], used when writing tags back out as keywords.
+///
+/// With a prefix set only tags carrying it are keywords; the rest were added in Urocissa and
+/// are not exported, so that reading the sidecar back does not turn them into `<prefix><tag>`.
+pub fn tag_to_keyword(tag: &str) -> Option<&str> {
+    strip_keyword_prefix(tag, &SERVER_CONFIG.metadata.keyword_tag_prefix)
+}
+
+fn prefix_keyword(keyword: &str, prefix: &str) -> String {
+    if keyword.starts_with(prefix) {
+        keyword.to_string()
+    } else {
+        format!("{}{}", prefix, keyword)
+    }
+}
+
+fn strip_keyword_prefix<'a>(tag: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        Some(tag)
+    } else {
+        tag.strip_prefix(prefix)
+    }
+}
+
+/// The first `<x:xmpmeta>` packet in a file (JPEG APP1, TIFF, PNG iTXt and WebP all keep it as text).
+fn read_packet(bytes: &[u8]) -> Option<String> {
+    let start = find(bytes, b"<x:xmpmeta")?;
+    let end = find(&bytes[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+    String::from_utf8(bytes[start..end].to_vec()).ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+pub fn parse_xmp(text: &str) -> XmpMetadata {
+    let packet = RE_XMP_PACKET
+        .find(text)
+        .map_or(text, |packet| packet.as_str());
+    let rating = RE_RATING
+        .captures(packet)
+        .and_then(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .and_then(|value| value.as_str().parse::<i32>().ok())
+        .map(|rating| rating.clamp(0, 5) as u8);
+    let label = RE_LABEL
+        .captures(packet)
+        .and_then(|caps| caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)))
+        .map(|value| xml_unescape(value.as_str().trim()))
+        .filter(|label| !label.is_empty());
+    let subject = RE_SUBJECT
+        .captures(packet)
+        .map(|caps| {
+            RE_LIST_ITEM
+                .captures_iter(&caps[1])
+                .map(|item| xml_unescape(item[1].trim()))
+                .filter(|keyword| !keyword.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let alt_text = |re: &Regex| {
+        re.captures(packet)
+            .map(|caps| xml_unescape(caps[1].trim()))
+            .filter(|text| !text.is_empty())
+    };
+    XmpMetadata {
+        rating,
+        label,
+        subject,
+        description: alt_text(&RE_DESCRIPTION),
+        title: alt_text(&RE_TITLE),
+        modified: None,
+    }
+}
+
+/// `photo.jpg.xmp` (darktable) or `photo.xmp` (Lightroom), whichever exists.
+pub fn existing_sidecar_path(source: &Path) -> Option<PathBuf> {
+    [sidecar_path(source), source.with_extension("xmp")]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Where a new sidecar is created: `photo.jpg.xmp`, so that `photo.jpg` and `photo.cr2` do not share one.
+pub fn sidecar_path(source: &Path) -> PathBuf {
+    let mut file_name = source.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".xmp");
+    source.with_file_name(file_name)
+}
+
+pub fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_attribute_form() {
+        let xmp = parse_xmp(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description
+                xmp:Rating="4" xmp:Label='Red'>
+                <dc:subject><rdf:Bag><rdf:li>beach</rdf:li><rdf:li> Tom &amp; Jerry </rdf:li><rdf:li/></rdf:Bag></dc:subject>
+                <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Title</rdf:li></rdf:Alt></dc:title>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#,
+        );
+        assert_eq!(
+            xmp,
+            XmpMetadata {
+                rating: Some(4),
+                label: Some("Red".to_string()),
+                subject: vec!["beach".to_string(), "Tom & Jerry".to_string()],
+                description: None,
+                title: Some("Title".to_string()),
+                modified: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_element_form_and_clamps_rejected() {
+        let xmp = parse_xmp(
+            "<rdf:Description><xmp:Rating>-1</xmp:Rating><xmp:Label>Green</xmp:Label>\
+             <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">a &lt;b&gt;</rdf:li></rdf:Alt></dc:description>\
+             </rdf:Description>",
+        );
+        assert_eq!(xmp.rating, Some(0));
+        assert_eq!(xmp.label.as_deref(), Some("Green"));
+        assert_eq!(xmp.description.as_deref(), Some("a <b>"));
+        assert!(xmp.subject.is_empty());
+    }
+
+    #[test]
+    fn reads_embedded_packet() {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x10];
+        bytes.extend_from_slice(b"<x:xmpmeta xmp:Rating=\"2\"></x:xmpmeta>");
+        bytes.extend_from_slice(&[0xFF, 0xD9]);
+        let packet = read_packet(&bytes).unwrap();
+        assert_eq!(parse_xmp(&packet).rating, Some(2));
+        assert_eq!(read_packet(b"no packet here"), None);
+    }
+
+    #[test]
+    fn later_sources_override_field_by_field() {
+        let mut xmp = XmpMetadata {
+            rating: Some(1),
+            label: Some("Red".to_string()),
+            subject: vec!["a".to_string()],
+            ..Default::default()
+        };
+        xmp.overlay(XmpMetadata {
+            rating: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(xmp.rating, Some(3));
+        assert_eq!(xmp.label.as_deref(), Some("Red"));
+        assert_eq!(xmp.subject, ["a"]);
+    }
+
+    #[test]
+    fn in_app_edits_survive_older_files() {
+        let xmp = |modified| XmpMetadata {
+            rating: Some(2),
+            label: Some("Red".to_string()),
+            description: Some("From file".to_string()),
+            modified,
+            ..Default::default()
+        };
+        let mut database = Database {
+            rating: 5,
+            label: None,
+            description: Some("Edited".to_string()),
+            metadata_edited_at: Some(1_000),
+            ..Default::default()
+        };
+        apply_xmp(&mut database, xmp(Some(500)));
+        assert_eq!(database.rating, 5);
+        // Unset fields are still filled in
+        assert_eq!(database.label.as_deref(), Some("Red"));
+        assert_eq!(database.description.as_deref(), Some("Edited"));
+
+        apply_xmp(&mut database, xmp(Some(2_000)));
+        assert_eq!(database.rating, 2);
+        assert_eq!(database.description.as_deref(), Some("From file"));
+    }
+
+    #[test]
+    fn keywords_round_trip_through_prefix() {
+        for prefix in ["", "kw:"] {
+            let tag = prefix_keyword("beach", prefix);
+            assert_eq!(strip_keyword_prefix(&tag, prefix), Some("beach"));
+            // Reading an exported keyword back yields the same tag
+            assert_eq!(
+                prefix_keyword(strip_keyword_prefix(&tag, prefix).unwrap(), prefix),
+                tag
+            );
+        }
+        assert_eq!(prefix_keyword("kw:beach", "kw:"), "kw:beach");
+        assert_eq!(strip_keyword_prefix("family", "kw:"), None);
+        assert_eq!(strip_keyword_prefix("family", ""), Some("family"));
+    }
+
+    #[test]
+    fn sidecar_names() {
+        let source = Path::new("/photos/IMG_1.CR2");
+        assert_eq!(sidecar_path(source), Path::new("/photos/IMG_1.CR2.xmp"));
+    }
+}
@@ -1,9 +1,12 @@
+pub mod apply_tag_rules;
 pub mod fix_orientation;
 pub mod generate_compressed_video;
 pub mod generate_dynamic_image;
 pub mod generate_exif;
 pub mod generate_ffmpeg;
 pub mod generate_image_hash;
+pub mod generate_iptc;
 pub mod generate_thumbnail;
 pub mod generate_width_height;
+pub mod generate_xmp;
 pub mod video_ffprobe;
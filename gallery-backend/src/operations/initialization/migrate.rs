@@ -0,0 +1,123 @@
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::public::constant::redb::{
+    ALBUM_TABLE, DATA_TABLE, SHARE_ACCESS_TABLE, SHARE_STATS_TABLE, SHARE_VISITOR_TABLE,
+};
+use crate::public::db::tree::TREE;
+use crate::public::db::tree::share_access::count_share_access;
+use crate::public::structure::abstract_data::AbstractData;
+use redb::{ReadableTable, TableError};
+
+/// Move `_favorite`, `_archived`, `_hidden` and `_trashed` out of the tag sets into the typed flags.
+///
+/// Only rows still carrying such a tag are rewritten, so after the first run this is a read-only scan.
+/// Rows trashed before `trashed_at` existed start their retention period now.
+pub fn migrate_flag_tags() {
+    let now = get_current_timestamp_u64() as u128;
+    let txn = TREE.in_disk.begin_write().unwrap();
+    let mut migrated = 0;
+    {
+        let mut data_table = txn.open_table(DATA_TABLE).unwrap();
+        let database_list: Vec<_> = data_table
+            .iter()
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|(_, guard)| AbstractData::Database(guard.value()))
+            .collect();
+        for mut abstract_data in database_list {
+            if abstract_data.migrate_flag_tags(now)
+                && let AbstractData::Database(database) = &abstract_data
+            {
+                data_table.insert(&*database.hash, database).unwrap();
+                migrated += 1;
+            }
+        }
+
+        let mut album_table = txn.open_table(ALBUM_TABLE).unwrap();
+        let album_list: Vec<_> = album_table
+            .iter()
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|(_, guard)| AbstractData::Album(guard.value()))
+            .collect();
+        for mut abstract_data in album_list {
+            if abstract_data.migrate_flag_tags(now)
+                && let AbstractData::Album(album) = &abstract_data
+            {
+                album_table.insert(&*album.id, album).unwrap();
+                migrated += 1;
+            }
+        }
+    }
+    txn.commit().unwrap();
+    if migrated > 0 {
+        info!("Migrated flag tags of {} items and albums", migrated);
+    }
+}
+
+/// Hash share passwords that were stored in plain text before passwords were hashed.
+///
+/// Plain text passwords no longer verify, so this has to run before the server starts.
+pub fn migrate_share_passwords() {
+    let txn = TREE.in_disk.begin_write().unwrap();
+    let mut migrated = 0;
+    {
+        let mut album_table = txn.open_table(ALBUM_TABLE).unwrap();
+        let album_list: Vec<_> = album_table
+            .iter()
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|(_, guard)| guard.value())
+            .collect();
+        for mut album in album_list {
+            let mut changed = false;
+            for share in album.share_list.values_mut() {
+                if share
+                    .migrate_plaintext_password()
+                    .expect("Failed to hash share password")
+                {
+                    changed = true;
+                    migrated += 1;
+                }
+            }
+            if changed {
+                album_table.insert(&*album.id, &album).unwrap();
+            }
+        }
+    }
+    txn.commit().unwrap();
+    if migrated > 0 {
+        info!("Hashed {} plain text share passwords", migrated);
+    }
+}
+
+/// Build the share totals from the access log, once, for logs written before totals were kept.
+pub fn migrate_share_stats() {
+    let read_txn = TREE.in_disk.begin_read().unwrap();
+    match read_txn.open_table(SHARE_STATS_TABLE) {
+        Err(TableError::TableDoesNotExist(_)) => {}
+        result => {
+            result.unwrap();
+            return;
+        }
+    }
+    drop(read_txn);
+
+    let txn = TREE.in_disk.begin_write().unwrap();
+    let mut migrated = 0;
+    {
+        let access_table = txn.open_table(SHARE_ACCESS_TABLE).unwrap();
+        let mut stats_table = txn.open_table(SHARE_STATS_TABLE).unwrap();
+        let mut visitor_table = txn.open_table(SHARE_VISITOR_TABLE).unwrap();
+        for (_, guard) in access_table.iter().unwrap().filter_map(|entry| entry.ok()) {
+            count_share_access(&mut stats_table, &mut visitor_table, &guard.value()).unwrap();
+            migrated += 1;
+        }
+    }
+    txn.commit().unwrap();
+    if migrated > 0 {
+        info!(
+            "Counted {} share access log entries into share totals",
+            migrated
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod ffmpeg;
 pub mod folder;
 pub mod logger;
+pub mod migrate;
 pub mod redb;
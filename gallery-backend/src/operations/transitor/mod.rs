@@ -68,14 +68,23 @@ pub fn clear_abstract_data_metadata(abstract_data: &mut AbstractData, show_metad
                 database.tag.clear();
                 database.album.clear();
                 database.alias.clear();
+                database.favorite = false;
+                database.rating = 0;
+                database.label = None;
+                database.description = None;
+                database.user_defined_metadata.clear();
             }
         }
         AbstractData::Album(album) => {
             if !show_metadata {
                 album.tag.clear();
+                album.favorite = false;
             }
         }
     }
+    if show_metadata {
+        abstract_data.expose_flag_tags();
+    }
 }
 
 pub fn abstract_data_to_database_timestamp_return(
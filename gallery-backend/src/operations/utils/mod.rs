@@ -1,2 +1,4 @@
 pub mod resize;
+pub mod sync_filter;
 pub mod timestamp;
+pub mod xmp_sidecar;
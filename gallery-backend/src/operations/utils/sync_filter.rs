@@ -0,0 +1,150 @@
+use crate::public::config::{PRIVATE_CONFIG, SERVER_CONFIG, SyncPathConfig};
+use glob::{MatchOptions, Pattern};
+use path_clean::PathClean;
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Compiled include/exclude rules of one sync path.
+pub struct SyncFilter {
+    root: PathBuf,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    min_file_size: u64,
+    ignore_hidden: bool,
+}
+
+/// Built when the server starts, so an invalid glob in config.json stops it right away.
+pub static SYNC_FILTER_LIST: LazyLock<Vec<SyncFilter>> = LazyLock::new(|| {
+    PRIVATE_CONFIG
+        .sync_path
+        .iter()
+        .map(|root| SyncFilter::new(root, &SERVER_CONFIG.sync.path_config(root)))
+        .collect()
+});
+
+/// The filter of the innermost sync path containing `path`, if any.
+pub fn sync_filter_for(path: &Path) -> Option<&'static SyncFilter> {
+    let path = path.clean();
+    SYNC_FILTER_LIST
+        .iter()
+        .filter(|filter| path.starts_with(&filter.root))
+        .max_by_key(|filter| filter.root.components().count())
+}
+
+impl SyncFilter {
+    pub fn new(root: &Path, config: &SyncPathConfig) -> Self {
+        let compile = |patterns: &[String]| -> Vec<Pattern> {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Pattern::new(pattern).unwrap_or_else(|err| {
+                        panic!(
+                            "Invalid glob {:?} for sync path {:?}: {}",
+                            pattern, root, err
+                        )
+                    })
+                })
+                .collect()
+        };
+        SyncFilter {
+            // `./photos` and `photos/` must both match `photos/a.jpg`
+            root: root.clean(),
+            include: compile(&config.include),
+            exclude: compile(&config.exclude),
+            min_file_size: config.min_file_size,
+            ignore_hidden: config.ignore_hidden,
+        }
+    }
+
+    /// Whether a walk should descend into `dir`.
+    pub fn allows_dir(&self, dir: &Path) -> bool {
+        match dir.clean().strip_prefix(&self.root) {
+            Ok(relative) => !self.is_hidden(relative) && !self.is_excluded(relative),
+            Err(_) => true,
+        }
+    }
+
+    pub fn allows_file(&self, path: &Path) -> bool {
+        let cleaned = path.clean();
+        let Ok(relative) = cleaned.strip_prefix(&self.root) else {
+            return true;
+        };
+        if self.is_hidden(relative) || self.is_excluded(relative) {
+            return false;
+        }
+        if !self.include.is_empty()
+            && !self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches_path_with(relative, MATCH_OPTIONS))
+        {
+            return false;
+        }
+        if self.min_file_size > 0 {
+            return path
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() >= self.min_file_size);
+        }
+        true
+    }
+
+    fn is_hidden(&self, relative: &Path) -> bool {
+        self.ignore_hidden
+            && relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+    }
+
+    /// `relative` or any of its parent directories matches an exclude pattern.
+    fn is_excluded(&self, relative: &Path) -> bool {
+        relative
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| {
+                self.exclude
+                    .iter()
+                    .any(|pattern| pattern.matches_path_with(ancestor, MATCH_OPTIONS))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(root: &str) -> SyncFilter {
+        SyncFilter::new(
+            Path::new(root),
+            &SyncPathConfig {
+                exclude: vec!["cache/**".to_string()],
+                ignore_hidden: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn relative_root_applies_rules() {
+        for root in ["./photos", "photos", "photos/"] {
+            let filter = filter(root);
+            assert!(
+                !filter.allows_file(Path::new("./photos/cache/a.jpg")),
+                "{root}"
+            );
+            assert!(
+                !filter.allows_file(Path::new("photos/.trash/a.jpg")),
+                "{root}"
+            );
+            assert!(!filter.allows_dir(Path::new("./photos/.git")), "{root}");
+            assert!(filter.allows_file(Path::new("photos/2024/a.jpg")), "{root}");
+        }
+    }
+}
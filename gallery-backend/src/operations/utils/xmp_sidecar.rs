@@ -0,0 +1,216 @@
+use crate::operations::indexation::generate_xmp::{
+    existing_sidecar_path, sidecar_path, tag_to_keyword, xml_escape,
+};
+use crate::public::config::{PRIVATE_CONFIG, SERVER_CONFIG};
+use crate::public::structure::database_struct::database::definition::Database;
+use anyhow::{Context, Result, bail};
+use path_clean::PathClean;
+use regex::Regex;
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::Path,
+    sync::LazyLock,
+};
+
+const EMPTY_SIDECAR: &str = "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\"/>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>
+";
+
+static RE_OLD_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\s+xmp:(?:Rating|Label)\s*=\s*(?:"[^"]*"|'[^']*')"#)
+        .expect("regex compilation failure")
+});
+static RE_OLD_ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?s)\s*(?:<xmp:Rating>.*?</xmp:Rating>|<xmp:Label>.*?</xmp:Label>|<dc:subject>.*?</dc:subject>|<dc:description>.*?</dc:description>|<xmp:Rating/>|<xmp:Label/>|<dc:subject/>|<dc:description/>)",
+    )
+    .expect("regex compilation failure")
+});
+static RE_DESCRIPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<rdf:Description\b([^>]*?)(/?)>").expect("regex compilation failure")
+});
+
+/// Write the item's rating, label, tags and caption to the XMP sidecar next to its source file.
+///
+/// Other properties in an existing sidecar are kept. Items without a live source inside a sync
+/// path have nowhere to put one and are skipped.
+pub fn write_sidecar(database: &Database) -> Result<()> {
+    if !SERVER_CONFIG.sync.write_xmp_sidecar {
+        return Ok(());
+    }
+    let Some(source) = database.live_source_path_opt() else {
+        return Ok(());
+    };
+    let source = source.clean();
+    if !PRIVATE_CONFIG
+        .sync_path
+        .iter()
+        .any(|root| source.starts_with(root.clean()))
+    {
+        return Ok(());
+    }
+
+    let path = existing_sidecar_path(&source).unwrap_or_else(|| sidecar_path(&source));
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound => EMPTY_SIDECAR.to_string(),
+        Err(err) => return Err(err).context(format!("failed to read sidecar {:?}", path)),
+    };
+
+    let mut subject: Vec<&str> = database
+        .tag
+        .iter()
+        .filter_map(|tag| tag_to_keyword(tag))
+        .collect();
+    subject.sort_unstable();
+    subject.dedup();
+    let updated = update_packet(&text, database, &subject)
+        .context(format!("failed to update sidecar {:?}", path))?;
+    write_atomically(&path, updated.as_bytes())
+        .context(format!("failed to write sidecar {:?}", path))?;
+    Ok(())
+}
+
+/// Write to a temporary file next to `path` and rename it over `path`, so readers such as
+/// darktable never see a half-written sidecar.
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let temp_path = path.with_file_name(file_name);
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+fn update_packet(text: &str, database: &Database, subject: &[&str]) -> Result<String> {
+    let text = RE_OLD_ATTRIBUTE.replace_all(text, "");
+    let text = RE_OLD_ELEMENT.replace_all(&text, "");
+
+    let Some(caps) = RE_DESCRIPTION.captures(&text) else {
+        bail!("no rdf:Description element");
+    };
+    let whole = caps.get(0).unwrap();
+    let is_self_closing = &caps[2] == "/";
+
+    let mut opening = format!("<rdf:Description{}", &caps[1]);
+    if !text.contains("xmlns:xmp=") {
+        opening.push_str(" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"");
+    }
+    if !text.contains("xmlns:dc=") {
+        opening.push_str(" xmlns:dc=\"http://purl.org/dc/elements/1.1/\"");
+    }
+    opening.push_str(&format!(" xmp:Rating=\"{}\"", database.rating));
+    if let Some(label) = &database.label {
+        opening.push_str(&format!(" xmp:Label=\"{}\"", xml_escape(label)));
+    }
+    opening.push('>');
+
+    if !subject.is_empty() {
+        opening.push_str("\n   <dc:subject>\n    <rdf:Bag>\n");
+        for keyword in subject {
+            opening.push_str(&format!("     <rdf:li>{}</rdf:li>\n", xml_escape(keyword)));
+        }
+        opening.push_str("    </rdf:Bag>\n   </dc:subject>");
+    }
+    if let Some(description) = &database.description {
+        opening.push_str(&format!(
+            "\n   <dc:description>\n    <rdf:Alt>\n     <rdf:li xml:lang=\"x-default\">{}</rdf:li>\n    </rdf:Alt>\n   </dc:description>",
+            xml_escape(description)
+        ));
+    }
+    if is_self_closing {
+        opening.push_str("\n  </rdf:Description>");
+    }
+
+    Ok(format!(
+        "{}{}{}",
+        &text[..whole.start()],
+        opening,
+        &text[whole.end()..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::indexation::generate_xmp::parse_xmp;
+
+    fn database() -> Database {
+        Database {
+            rating: 5,
+            label: Some("Blue".to_string()),
+            description: Some("Sunset & sea".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_through_empty_sidecar() {
+        let updated = update_packet(EMPTY_SIDECAR, &database(), &["beach", "<b>"]).unwrap();
+        let xmp = parse_xmp(&updated);
+        assert_eq!(xmp.rating, Some(5));
+        assert_eq!(xmp.label.as_deref(), Some("Blue"));
+        assert_eq!(xmp.subject, ["beach", "<b>"]);
+        assert_eq!(xmp.description.as_deref(), Some("Sunset & sea"));
+    }
+
+    #[test]
+    fn replaces_old_values_and_keeps_other_properties() {
+        let text = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:darktable="http://darktable.sf.net/"
+    xmp:Rating="1" xmp:Label="Red" darktable:history_end="3">
+   <dc:subject><rdf:Bag><rdf:li>old</rdf:li></rdf:Bag></dc:subject>
+   <darktable:history><rdf:Seq/></darktable:history>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+        let mut database = database();
+        database.label = None;
+        database.description = None;
+        let updated = update_packet(text, &database, &["new"]).unwrap();
+        let xmp = parse_xmp(&updated);
+        assert_eq!(xmp.rating, Some(5));
+        assert_eq!(xmp.label, None);
+        assert_eq!(xmp.subject, ["new"]);
+        assert!(updated.contains(r#"darktable:history_end="3""#));
+        assert!(updated.contains("<darktable:history><rdf:Seq/></darktable:history>"));
+        assert_eq!(updated.matches("xmlns:xmp=").count(), 1);
+        // Updating again changes nothing
+        assert_eq!(
+            update_packet(&updated, &database, &["new"]).unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn rejects_packet_without_description() {
+        assert!(update_packet("<x:xmpmeta></x:xmpmeta>", &database(), &[]).is_err());
+    }
+
+    #[test]
+    fn atomic_write_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("urocissa-xmp-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.jpg.xmp");
+        fs::write(&path, "old").unwrap();
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
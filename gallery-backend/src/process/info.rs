@@ -14,6 +14,7 @@ use crate::operations::indexation::generate_thumbnail::{
 use crate::operations::indexation::generate_width_height::{
     generate_image_width_height, generate_video_width_height,
 };
+use crate::operations::indexation::generate_xmp::{apply_xmp, generate_xmp};
 use crate::public::structure::database_struct::database::definition::Database;
 use anyhow::{Context, Result};
 
@@ -22,6 +23,9 @@ pub fn process_image_info(database: &mut Database) -> Result<()> {
     // EXIF metadata extraction (non‑fallible)
     database.exif_vec = generate_exif_for_image(database);
 
+    // Rating, label, keywords and caption from embedded IPTC/XMP or a sidecar (non‑fallible)
+    apply_xmp(database, generate_xmp(database));
+
     // Decode image to DynamicImage
     let mut dynamic_image =
         generate_dynamic_image(database).context("failed to decode image into DynamicImage")?;
@@ -47,8 +51,8 @@ pub fn process_image_info(database: &mut Database) -> Result<()> {
 /// Re‑build all metadata for an existing **image** (e.g. after replace / fix).
 pub fn regenerate_metadata_for_image(database: &mut Database) -> Result<()> {
     // Refresh size from filesystem
-    database.size = metadata(database.imported_path())
-        .context("failed to read metadata for imported image file")?
+    database.size = metadata(database.original_path())
+        .context("failed to read metadata for original image file")?
         .len();
 
     // Re‑run the full processing pipeline
@@ -62,6 +66,9 @@ pub fn process_video_info(database: &mut Database) -> Result<()> {
     database.exif_vec = generate_exif_for_video(database)
         .context("failed to extract video metadata via ffprobe")?;
 
+    // Rating, label, keywords and caption from a sidecar (non‑fallible)
+    apply_xmp(database, generate_xmp(database));
+
     // Get logical dimensions and fix if rotated
     (database.width, database.height) =
         generate_video_width_height(database).context("failed to obtain video width/height")?;
@@ -85,8 +92,8 @@ pub fn process_video_info(database: &mut Database) -> Result<()> {
 /// Re‑build all metadata for an existing **video** file.
 pub fn regenerate_metadata_for_video(database: &mut Database) -> Result<()> {
     // Refresh size from filesystem metadata
-    database.size = metadata(database.imported_path())
-        .context("failed to read metadata for imported video file")?
+    database.size = metadata(database.original_path())
+        .context("failed to read metadata for original video file")?
         .len();
 
     // Re‑run the full processing pipeline
@@ -1,14 +1,23 @@
+use std::sync::LazyLock;
 use tokio::sync::mpsc::UnboundedReceiver;
 
 use crate::operations::initialization::{
-    ffmpeg::check_ffmpeg_and_ffprobe, folder::initialize_folder, logger::initialize_logger,
+    ffmpeg::check_ffmpeg_and_ffprobe,
+    folder::initialize_folder,
+    logger::initialize_logger,
+    migrate::{migrate_flag_tags, migrate_share_passwords, migrate_share_stats},
     redb::initialize_file,
 };
+use crate::operations::utils::sync_filter::SYNC_FILTER_LIST;
 
 pub fn initialize() -> UnboundedReceiver<String> {
     let rx = initialize_logger();
     check_ffmpeg_and_ffprobe();
     initialize_folder();
+    LazyLock::force(&SYNC_FILTER_LIST);
     initialize_file();
+    migrate_flag_tags();
+    migrate_share_passwords();
+    migrate_share_stats();
     rx
 }
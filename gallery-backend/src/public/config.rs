@@ -1,10 +1,13 @@
+use crate::public::structure::expression::Expression;
+use arrayvec::ArrayString;
 use dotenv::dotenv;
-use serde::{Deserialize, Serialize};
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::{self, File},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::LazyLock,
 };
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash, Default)]
@@ -14,7 +17,260 @@ pub struct PublicConfig {
     pub disable_img: bool,
 }
 
-pub static PUBLIC_CONFIG: LazyLock<PublicConfig> = LazyLock::new(|| {
+pub static PUBLIC_CONFIG: LazyLock<PublicConfig> = LazyLock::new(read_config_json);
+
+/// Server-side settings that also live in `config.json` but are never sent to clients.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerConfig {
+    pub job: JobConfig,
+    pub sync: SyncConfig,
+    pub storage: StorageConfig,
+    pub gc: GcConfig,
+    pub trash: TrashConfig,
+    pub metadata: MetadataConfig,
+    pub auto_tag: AutoTagConfig,
+    pub events: EventsConfig,
+    pub share: ShareConfig,
+}
+
+/// Backend for `./object`; credentials for S3 come from the environment.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(
+    tag = "backend",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum StorageConfig {
+    #[default]
+    Local,
+    S3 {
+        /// e.g. `http://127.0.0.1:9000`
+        endpoint: String,
+        bucket: String,
+        region: String,
+        /// Prepended to every object key.
+        #[serde(default)]
+        prefix: String,
+        /// Size limit of the local download cache in `./object/cache`.
+        #[serde(default = "default_cache_limit_mb")]
+        cache_limit_mb: u64,
+    },
+}
+
+fn default_cache_limit_mb() -> u64 {
+    4096
+}
+
+impl StorageConfig {
+    pub fn cache_limit_bytes(&self) -> u64 {
+        match self {
+            StorageConfig::Local => 0,
+            StorageConfig::S3 { cache_limit_mb, .. } => cache_limit_mb * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncConfig {
+    /// Tag added to items whose every synced source file has been deleted or moved away.
+    pub missing_source_tag: Option<String>,
+    /// Write rating, label and tag edits to an XMP sidecar next to the source file.
+    ///
+    /// Off by default: it modifies the synced folders, which may be shared with other tools.
+    pub write_xmp_sidecar: bool,
+    /// Per sync path overrides, keyed by the path as given in `SYNC_PATH`.
+    pub paths: HashMap<PathBuf, SyncPathConfig>,
+}
+
+impl SyncConfig {
+    pub fn path_config(&self, sync_path: &Path) -> SyncPathConfig {
+        let sync_path = sync_path.clean();
+        self.paths
+            .iter()
+            .find(|(path, _)| path.clean() == sync_path)
+            .map(|(_, config)| config.clone())
+            .unwrap_or_default()
+    }
+
+    /// The settings of the innermost configured sync path containing `file`.
+    pub fn config_for_file(&self, file: &Path) -> SyncPathConfig {
+        self.paths
+            .iter()
+            .filter(|(path, _)| file.starts_with(path.clean()))
+            .max_by_key(|(path, _)| path.components().count())
+            .map(|(_, config)| config.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncPathConfig {
+    pub watch: WatchMode,
+    /// Globs relative to the sync path; when non-empty a file must match one of them.
+    pub include: Vec<String>,
+    /// Globs relative to the sync path. A pattern matching a directory excludes everything below it.
+    pub exclude: Vec<String>,
+    /// Files smaller than this many bytes are skipped.
+    pub min_file_size: u64,
+    /// Skip files and directories whose name starts with a dot.
+    pub ignore_hidden: bool,
+    /// Index files in place instead of copying them into `./object/imported`.
+    pub reference_only: bool,
+}
+
+/// How changes below a sync path are detected.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(
+    tag = "mode",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WatchMode {
+    /// inotify / FSEvents / ReadDirectoryChangesW.
+    #[default]
+    Native,
+    /// Stat the whole tree every `interval_secs`; for NFS, SMB and FUSE mounts.
+    Poll { interval_secs: u64 },
+    /// No watcher at all; reconcile the tree every `interval_secs`.
+    Rescan { interval_secs: u64 },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JobConfig {
+    /// A job is given up after this many failed attempts.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every further failure.
+    pub retry_backoff_ms: u64,
+    pub max_retry_backoff_ms: u64,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_backoff_ms: 30_000,
+            max_retry_backoff_ms: 60 * 60 * 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GcConfig {
+    /// Hours between sweeps for unreferenced objects; `0` disables the periodic sweep.
+    pub interval_hours: u64,
+    /// Only log what would be reclaimed.
+    pub dry_run: bool,
+    /// Move unreferenced objects under `quarantine/` instead of deleting them.
+    pub quarantine: bool,
+    /// Objects written more recently than this are never swept, so an import that is about to
+    /// commit its row keeps its files.
+    pub min_object_age_minutes: u64,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            interval_hours: 24,
+            dry_run: false,
+            quarantine: false,
+            min_object_age_minutes: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrashConfig {
+    /// Trashed items and albums are deleted for good after this many days; `0` keeps them forever.
+    pub retention_days: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MetadataConfig {
+    /// Prepended to IPTC and XMP keywords when they become tags, e.g. `kw:`.
+    /// When set, only tags with the prefix are written back to sidecars as keywords.
+    pub keyword_tag_prefix: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoTagConfig {
+    /// Checked against every item once its metadata has been extracted.
+    pub rules: Vec<AutoTagRule>,
+}
+
+/// Add `tags` and `albums` to items matching `when`; rules only ever add.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoTagRule {
+    /// Condition written like a search query, e.g. `{"Path": "/Scans/"}`.
+    pub when: Expression,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Album ids; albums that no longer exist are skipped.
+    #[serde(default)]
+    pub albums: Vec<ArrayString<64>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventsConfig {
+    /// Hours between runs of the event clustering job; `0` only runs it on request.
+    pub interval_hours: u64,
+    /// A longer pause between consecutive items starts a new event.
+    pub gap_hours: u64,
+    /// Consecutive geotagged items further apart than this start a new event.
+    pub max_distance_km: u64,
+    /// Smaller clusters are not proposed.
+    pub min_items: usize,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            interval_hours: 0,
+            gap_hours: 8,
+            max_distance_km: 50,
+            min_items: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShareConfig {
+    /// Share access log entries older than this are dropped; `0` keeps them forever.
+    pub access_log_retention_days: u64,
+    /// The server sits behind a reverse proxy that sets Rocket's `ip_header` (`X-Real-IP` by
+    /// default). Only then is that header used as the client address for password lockouts and
+    /// the share access log; otherwise anyone could pick their own address.
+    pub trusted_proxy: bool,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            access_log_retention_days: 90,
+            trusted_proxy: false,
+        }
+    }
+}
+
+pub static SERVER_CONFIG: LazyLock<ServerConfig> = LazyLock::new(read_config_json);
+
+fn read_config_json<T: DeserializeOwned + Default>() -> T {
     // Attempt to open the config.json file
     let file = File::open("config.json");
 
@@ -26,14 +282,14 @@ pub static PUBLIC_CONFIG: LazyLock<PublicConfig> = LazyLock::new(|| {
         Err(err) => {
             if err.kind() == io::ErrorKind::NotFound {
                 info!("config.json not found. Using default configuration.");
-                PublicConfig::default()
+                T::default()
             } else {
                 // For other errors, panic and provide the error message
                 panic!("Failed to open config.json: {}", err);
             }
         }
     }
-});
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct PrivateConfig {
@@ -41,6 +297,8 @@ pub struct PrivateConfig {
     pub sync_path: HashSet<PathBuf>,
     pub auth_key: Option<String>,
     pub discord_hook_url: Option<String>,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
 }
 pub static PRIVATE_CONFIG: LazyLock<PrivateConfig> = LazyLock::new(|| {
     dotenv().ok();
@@ -53,6 +311,14 @@ pub static PRIVATE_CONFIG: LazyLock<PrivateConfig> = LazyLock::new(|| {
             result.discord_hook_url = None;
         }
     }
+    for secret in [
+        &mut result.s3_access_key_id,
+        &mut result.s3_secret_access_key,
+    ] {
+        if secret.as_ref().is_some_and(|value| value.trim().is_empty()) {
+            *secret = None;
+        }
+    }
 
     let upload_path =
         fs::canonicalize(PathBuf::from("./upload")).expect("canonicalize(\"./upload\") failed");
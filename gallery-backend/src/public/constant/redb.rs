@@ -1,7 +1,27 @@
 use redb::TableDefinition;
 
-use crate::public::structure::{album::Album, database_struct::database::definition::Database};
+use crate::public::structure::{
+    album::Album,
+    database_struct::database::definition::Database,
+    event_proposal::EventProposal,
+    share_access::{ShareAccess, ShareStats},
+};
 
 pub const DATA_TABLE: TableDefinition<&str, Database> = TableDefinition::new("database");
 
 pub const ALBUM_TABLE: TableDefinition<&str, Album> = TableDefinition::new("album");
+
+pub const EVENT_PROPOSAL_TABLE: TableDefinition<&str, EventProposal> =
+    TableDefinition::new("event_proposal");
+
+/// Keyed by share id and the time of access in nanoseconds, so one share's log is a range.
+pub const SHARE_ACCESS_TABLE: TableDefinition<(&str, u128), ShareAccess> =
+    TableDefinition::new("share_access");
+
+/// Running totals per share id, kept up to date as accesses are logged.
+pub const SHARE_STATS_TABLE: TableDefinition<&str, ShareStats> =
+    TableDefinition::new("share_stats");
+
+/// First access in milliseconds per share id and client address; counts distinct visitors.
+pub const SHARE_VISITOR_TABLE: TableDefinition<(&str, &str), u128> =
+    TableDefinition::new("share_visitor");
@@ -1,8 +1,14 @@
 use crate::router::get::get_prefetch::Prefetch;
 
 use crate::public::structure::{
-    album::Album, database_struct::database::definition::Database, reduced_data::ReducedData,
+    album::Album,
+    database_struct::database::definition::Database,
+    event_proposal::EventProposal,
+    job::Job,
+    legacy::{AlbumV16, DatabaseV16},
+    reduced_data::ReducedData,
     row::Row,
+    share_access::{ShareAccess, ShareStats},
 };
 use redb::{TypeName, Value};
 
@@ -23,7 +29,9 @@ impl Value for Database {
     where
         Self: 'a,
     {
-        bitcode::decode::<Self>(data).expect("Failed to deserialize Database")
+        bitcode::decode::<Self>(data)
+            .or_else(|_| bitcode::decode::<DatabaseV16>(data).map(Database::from))
+            .expect("Failed to deserialize Database")
     }
 
     fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
@@ -115,7 +123,9 @@ impl Value for Album {
     where
         Self: 'a,
     {
-        bitcode::decode::<Self>(data).expect("Failed to deserialize Album")
+        bitcode::decode::<Self>(data)
+            .or_else(|_| bitcode::decode::<AlbumV16>(data).map(Album::from))
+            .expect("Failed to deserialize Album")
     }
 
     fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
@@ -155,3 +165,119 @@ impl Value for Prefetch {
         TypeName::new("Prefetch")
     }
 }
+
+impl Value for Job {
+    type SelfType<'a>
+        = Self
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        bitcode::decode::<Self>(data).expect("Failed to deserialize Job")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
+        bitcode::encode(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("Job")
+    }
+}
+
+impl Value for EventProposal {
+    type SelfType<'a>
+        = Self
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        bitcode::decode::<Self>(data).expect("Failed to deserialize EventProposal")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
+        bitcode::encode(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("EventProposal")
+    }
+}
+
+impl Value for ShareAccess {
+    type SelfType<'a>
+        = Self
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        bitcode::decode::<Self>(data).expect("Failed to deserialize ShareAccess")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
+        bitcode::encode(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("ShareAccess")
+    }
+}
+
+impl Value for ShareStats {
+    type SelfType<'a>
+        = Self
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        bitcode::decode::<Self>(data).expect("Failed to deserialize ShareStats")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a> {
+        bitcode::encode(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("ShareStats")
+    }
+}
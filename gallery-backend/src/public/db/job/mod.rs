@@ -0,0 +1,14 @@
+use crate::public::structure::job::Job;
+use redb::TableDefinition;
+use std::sync::LazyLock;
+pub mod new;
+pub mod read_jobs;
+pub mod update_job;
+pub static JOB_TABLE_DEFINITION: TableDefinition<&str, Job> = TableDefinition::new("job_table"); // source path -> job
+
+#[derive(Debug)]
+pub struct JobQueue {
+    pub in_disk: &'static redb::Database,
+}
+
+pub static JOB_QUEUE: LazyLock<JobQueue> = LazyLock::new(JobQueue::new);
@@ -0,0 +1,15 @@
+use std::sync::LazyLock;
+
+use super::JobQueue;
+
+// Unlike the cache databases this one is kept across restarts, so unfinished jobs can be resumed.
+static JOB_IN_DISK: LazyLock<redb::Database> =
+    LazyLock::new(|| redb::Database::create("./db/job_db.redb").unwrap());
+
+impl JobQueue {
+    pub fn new() -> Self {
+        JobQueue {
+            in_disk: &JOB_IN_DISK,
+        }
+    }
+}
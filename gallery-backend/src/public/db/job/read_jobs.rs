@@ -0,0 +1,21 @@
+use super::{JOB_TABLE_DEFINITION, JobQueue};
+use crate::public::structure::job::Job;
+use anyhow::Result;
+use redb::ReadableTable;
+
+impl JobQueue {
+    pub fn read_jobs(&self) -> Result<Vec<Job>> {
+        let read_txn = self.in_disk.begin_read()?;
+        let table = match read_txn.open_table(JOB_TABLE_DEFINITION) {
+            Ok(table) => table,
+            // Nothing has ever been queued.
+            Err(redb::TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let jobs = table
+            .iter()?
+            .map(|entry| entry.map(|(_, value)| value.value()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(jobs)
+    }
+}
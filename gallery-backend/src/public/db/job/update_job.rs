@@ -0,0 +1,114 @@
+use super::{JOB_TABLE_DEFINITION, JobQueue};
+use crate::{
+    operations::utils::timestamp::get_current_timestamp_u64,
+    public::{
+        config::SERVER_CONFIG,
+        structure::job::{Job, JobStage},
+    },
+};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use redb::{Durability, ReadableTable};
+use std::path::Path;
+
+impl JobQueue {
+    /// Records that `job` is (re)starting, keeping the attempt history of an earlier run.
+    pub fn begin(&self, mut job: Job) -> Result<()> {
+        let write_txn = self.in_disk.begin_write()?;
+        {
+            let mut table = write_txn.open_table(JOB_TABLE_DEFINITION)?;
+            if let Some(previous) = table.get(job.path.as_str())?.map(|guard| guard.value()) {
+                job.attempts = previous.attempts;
+                job.last_error = previous.last_error;
+                job.created_time = previous.created_time;
+                job.presigned_album_id_opt = job
+                    .presigned_album_id_opt
+                    .or(previous.presigned_album_id_opt);
+            }
+            job.retry_at = None;
+            table.insert(job.path.as_str(), &job)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Moves the job for `path` on to `stage`.
+    pub fn advance(
+        &self,
+        path: &Path,
+        stage: JobStage,
+        hash: Option<ArrayString<64>>,
+    ) -> Result<()> {
+        let key = path.to_string_lossy();
+        let mut write_txn = self.in_disk.begin_write()?;
+        // Every stage is safe to redo, so losing the latest transition in a crash only costs
+        // repeating some work. Skip the fsync to keep per-file overhead low.
+        write_txn.set_durability(Durability::Eventual);
+        {
+            let mut table = write_txn.open_table(JOB_TABLE_DEFINITION)?;
+            let job_opt = table.get(&*key)?.map(|guard| guard.value());
+            if let Some(mut job) = job_opt {
+                job.stage = stage;
+                job.hash = hash.or(job.hash);
+                job.updated_time = get_current_timestamp_u64();
+                table.insert(&*key, &job)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Records a failed attempt.
+    ///
+    /// Returns the delay before the job should be retried, or `None` once it has used up
+    /// `job.maxAttempts` and is given up. Given-up jobs stay in the table for inspection.
+    pub fn fail(&self, path: &Path, err: &anyhow::Error) -> Result<Option<u64>> {
+        let key = path.to_string_lossy();
+        let config = &SERVER_CONFIG.job;
+        let write_txn = self.in_disk.begin_write()?;
+        let mut delay_opt = None;
+        {
+            let mut table = write_txn.open_table(JOB_TABLE_DEFINITION)?;
+            let job_opt = table.get(&*key)?.map(|guard| guard.value());
+            if let Some(mut job) = job_opt {
+                let now = get_current_timestamp_u64();
+                job.attempts += 1;
+                job.last_error = Some(format!("{:#}", err));
+                job.updated_time = now;
+                if job.attempts < config.max_attempts {
+                    let delay = config
+                        .retry_backoff_ms
+                        .saturating_mul(1 << (job.attempts - 1).min(32))
+                        .min(config.max_retry_backoff_ms);
+                    job.retry_at = Some(now.saturating_add(delay));
+                    delay_opt = Some(delay);
+                } else {
+                    job.retry_at = None;
+                    error!(
+                        "Giving up on {} after {} attempts: {}",
+                        job.path,
+                        job.attempts,
+                        job.last_error.as_deref().unwrap_or_default()
+                    );
+                }
+                table.insert(&*key, &job)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(delay_opt)
+    }
+
+    pub fn finish(&self, path: &Path) -> Result<()> {
+        let key = path.to_string_lossy();
+        let mut write_txn = self.in_disk.begin_write()?;
+        // A finished job that reappears after a crash is indexed again and deduplicated by hash,
+        // so only `begin` needs to reach the disk before work starts.
+        write_txn.set_durability(Durability::Eventual);
+        {
+            let mut table = write_txn.open_table(JOB_TABLE_DEFINITION)?;
+            table.remove(&*key)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 pub mod expire;
+pub mod job;
 pub mod query_snapshot;
 pub mod tree;
 pub mod tree_snapshot;
@@ -0,0 +1,131 @@
+use super::Tree;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::database_struct::database_timestamp::DatabaseTimestamp;
+use anyhow::Result;
+use arrayvec::ArrayString;
+use std::collections::{HashMap, HashSet};
+
+impl Tree {
+    /// `album_id` and every album nested below it.
+    pub fn album_subtree(&self, album_id: ArrayString<64>) -> HashSet<ArrayString<64>> {
+        let ref_data = self.in_memory.read().unwrap();
+        album_subtree_in(&ref_data, album_id)
+    }
+}
+
+/// Same as [`Tree::album_subtree`], for callers already holding the in-memory tree.
+pub fn album_subtree_in(
+    data: &[DatabaseTimestamp],
+    album_id: ArrayString<64>,
+) -> HashSet<ArrayString<64>> {
+    let mut children: HashMap<ArrayString<64>, Vec<ArrayString<64>>> = HashMap::new();
+    for database_timestamp in data {
+        if let AbstractData::Album(album) = &database_timestamp.abstract_data
+            && let Some(parent_id) = album.parent_id
+        {
+            children.entry(parent_id).or_default().push(album.id);
+        }
+    }
+
+    let mut subtree = HashSet::from([album_id]);
+    let mut stack = vec![album_id];
+    while let Some(id) = stack.pop() {
+        for child_id in children.get(&id).into_iter().flatten() {
+            if subtree.insert(*child_id) {
+                stack.push(*child_id);
+            }
+        }
+    }
+    subtree
+}
+
+/// Whether filing `album_id` under `parent_id_opt` would put it inside itself.
+///
+/// `parent_of` looks up the current parent of an album. Walks up from the new parent; an
+/// existing loop above it is not followed forever.
+pub fn creates_cycle(
+    album_id: ArrayString<64>,
+    parent_id_opt: Option<ArrayString<64>>,
+    mut parent_of: impl FnMut(ArrayString<64>) -> Result<Option<ArrayString<64>>>,
+) -> Result<bool> {
+    let mut ancestor_id_opt = parent_id_opt;
+    let mut visited = HashSet::new();
+    while let Some(ancestor_id) = ancestor_id_opt {
+        if ancestor_id == album_id {
+            return Ok(true);
+        }
+        if !visited.insert(ancestor_id) {
+            break;
+        }
+        ancestor_id_opt = parent_of(ancestor_id)?;
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::structure::album::Album;
+
+    fn id(name: &str) -> ArrayString<64> {
+        ArrayString::from(name).unwrap()
+    }
+
+    fn album(name: &str, parent: Option<&str>) -> DatabaseTimestamp {
+        DatabaseTimestamp {
+            abstract_data: AbstractData::Album(Album {
+                id: id(name),
+                parent_id: parent.map(id),
+                ..Default::default()
+            }),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn subtree_includes_every_level() {
+        let data = [
+            album("root", None),
+            album("a", Some("root")),
+            album("b", Some("a")),
+            album("c", Some("root")),
+            album("other", None),
+        ];
+        assert_eq!(
+            album_subtree_in(&data, id("root")),
+            HashSet::from([id("root"), id("a"), id("b"), id("c")])
+        );
+        assert_eq!(
+            album_subtree_in(&data, id("a")),
+            HashSet::from([id("a"), id("b")])
+        );
+        assert_eq!(album_subtree_in(&data, id("b")), HashSet::from([id("b")]));
+    }
+
+    #[test]
+    fn subtree_terminates_on_loop() {
+        let data = [album("a", Some("b")), album("b", Some("a"))];
+        assert_eq!(
+            album_subtree_in(&data, id("a")),
+            HashSet::from([id("a"), id("b")])
+        );
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let parent_map = HashMap::from([
+            (id("a"), None),
+            (id("b"), Some(id("a"))),
+            (id("c"), Some(id("b"))),
+            // An existing loop that does not involve `a`
+            (id("x"), Some(id("y"))),
+            (id("y"), Some(id("x"))),
+        ]);
+        let parent_of = |album_id: ArrayString<64>| Ok(parent_map[&album_id]);
+        assert!(creates_cycle(id("a"), Some(id("c")), parent_of).unwrap());
+        assert!(creates_cycle(id("a"), Some(id("a")), parent_of).unwrap());
+        assert!(!creates_cycle(id("c"), Some(id("a")), parent_of).unwrap());
+        assert!(!creates_cycle(id("a"), None, parent_of).unwrap());
+        assert!(!creates_cycle(id("a"), Some(id("x")), parent_of).unwrap());
+    }
+}
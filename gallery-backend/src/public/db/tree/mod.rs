@@ -1,5 +1,8 @@
+pub mod album_tree;
 pub mod new;
 pub mod read_tags;
+pub mod rewrite_tags;
+pub mod share_access;
 
 use crate::public::structure::database_struct::database_timestamp::DatabaseTimestamp;
 use std::sync::{Arc, LazyLock, RwLock, atomic::AtomicU64};
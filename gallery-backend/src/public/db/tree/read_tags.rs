@@ -1,9 +1,11 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::public::structure::tag_tree::{parent_tag, tag_ancestors};
 use crate::{public::constant::redb::ALBUM_TABLE, public::structure::album::Album};
 use anyhow::{Context, Result};
 use dashmap::DashMap;
-use rayon::iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator};
+use rayon::iter::{ParallelBridge, ParallelIterator};
 use redb::ReadableTable;
 use serde::{Deserialize, Serialize};
 
@@ -12,12 +14,72 @@ use super::Tree;
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub struct TagInfo {
     pub tag: String,
+    /// Items and albums carrying exactly this tag.
     pub number: usize,
+    /// Items and albums carrying this tag or any tag below it, each counted once.
+    pub total: usize,
+    pub parent: Option<String>,
+    pub children: Vec<String>,
+}
+
+/// Concurrent tag counts, including the intermediate nodes of hierarchical tags.
+#[derive(Default)]
+pub struct TagCounter {
+    counts: DashMap<String, (AtomicUsize, AtomicUsize)>,
+}
+
+impl TagCounter {
+    /// Count the tag set of one item or album.
+    pub fn add(&self, tags: &HashSet<String>) {
+        let mut subtree = HashSet::new();
+        for tag in tags {
+            self.counts
+                .entry(tag.clone())
+                .or_default()
+                .0
+                .fetch_add(1, Ordering::Relaxed);
+            subtree.insert(tag.as_str());
+            subtree.extend(tag_ancestors(tag));
+        }
+        for node in subtree {
+            self.counts
+                .entry(node.to_string())
+                .or_default()
+                .1
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn into_tag_infos(self) -> Vec<TagInfo> {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in self.counts.iter() {
+            if let Some(parent) = parent_tag(entry.key()) {
+                children
+                    .entry(parent.to_string())
+                    .or_default()
+                    .push(entry.key().clone());
+            }
+        }
+        self.counts
+            .into_iter()
+            .map(|(tag, (number, total))| {
+                let mut children = children.remove(&tag).unwrap_or_default();
+                children.sort_unstable();
+                TagInfo {
+                    parent: parent_tag(&tag).map(str::to_string),
+                    number: number.into_inner(),
+                    total: total.into_inner(),
+                    children,
+                    tag,
+                }
+            })
+            .collect()
+    }
 }
 
 impl Tree {
     pub fn read_tags(&'static self) -> Vec<TagInfo> {
-        let tag_counts = DashMap::new();
+        let tag_counter = TagCounter::default();
 
         self.in_memory
             .read()
@@ -25,22 +87,10 @@ impl Tree {
             .iter()
             .par_bridge()
             .for_each(|database_timestamp| {
-                for tag in database_timestamp.abstract_data.tag() {
-                    let counter = tag_counts
-                        .entry(tag.clone())
-                        .or_insert_with(|| AtomicUsize::new(0));
-                    counter.fetch_add(1, Ordering::Relaxed);
-                }
+                tag_counter.add(database_timestamp.abstract_data.tag());
             });
 
-        let tag_infos = tag_counts
-            .par_iter()
-            .map(|entry| TagInfo {
-                tag: entry.key().clone(),
-                number: entry.value().load(Ordering::Relaxed),
-            })
-            .collect();
-        tag_infos
+        tag_counter.into_tag_infos()
     }
     pub fn read_albums(&self) -> Result<Vec<Album>> {
         Ok(
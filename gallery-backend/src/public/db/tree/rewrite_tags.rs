@@ -0,0 +1,115 @@
+use super::Tree;
+use crate::public::constant::redb::{ALBUM_TABLE, DATA_TABLE};
+use crate::public::structure::database_struct::database::definition::Database;
+use crate::public::structure::flag::Flag;
+use crate::public::structure::tag_tree::{retarget_tag, tag_is_within};
+use anyhow::{Context, Result, bail};
+use redb::ReadableTable;
+use std::collections::HashSet;
+
+impl Tree {
+    /// Replace every tag in `from` with `to` on all items and albums in one write transaction.
+    /// Tags below a replaced one move along, so `places/japan` to `trips` turns
+    /// `places/japan/kyoto` into `trips/kyoto`.
+    ///
+    /// Returns the rewritten items so callers can mirror the change elsewhere, e.g. into sidecars.
+    pub fn rewrite_tags(&self, from: &HashSet<String>, to: &str) -> Result<Vec<Database>> {
+        if to.is_empty() {
+            bail!("Target tag must not be empty");
+        }
+        if let Some(tag) = from
+            .iter()
+            .map(String::as_str)
+            .chain([to])
+            .find(|tag| Flag::from_tag(tag).is_some())
+        {
+            bail!("{} is a built-in flag and cannot be renamed", tag);
+        }
+        if let Some(source) = from
+            .iter()
+            .find(|source| source.as_str() != to && tag_is_within(to, source))
+        {
+            bail!("Cannot move {} into its own subtree {}", source, to);
+        }
+
+        // The most specific source wins when both a node and one of its descendants are given
+        let retarget = |tag: &str| {
+            from.iter()
+                .filter(|source| tag_is_within(tag, source))
+                .max_by_key(|source| source.len())
+                .and_then(|source| retarget_tag(tag, source, to))
+        };
+        let rewrite = |tag_set: &mut HashSet<String>| -> bool {
+            let moved: Vec<(String, String)> = tag_set
+                .iter()
+                .filter_map(|tag| retarget(tag).map(|new_tag| (tag.clone(), new_tag)))
+                .collect();
+            for (old_tag, _) in &moved {
+                tag_set.remove(old_tag);
+            }
+            let changed = !moved.is_empty();
+            tag_set.extend(moved.into_iter().map(|(_, new_tag)| new_tag));
+            changed
+        };
+
+        let write_txn = self
+            .in_disk
+            .begin_write()
+            .context("Failed to begin write transaction")?;
+        let mut rewritten = Vec::new();
+        let mut rewritten_album_count = 0;
+        {
+            let mut data_table = write_txn
+                .open_table(DATA_TABLE)
+                .context("Failed to open DATA_TABLE")?;
+            let database_list: Vec<Database> = data_table
+                .iter()
+                .context("Failed to create iterator over DATA_TABLE")?
+                .filter_map(|entry| entry.ok())
+                .map(|(_, guard)| guard.value())
+                .filter(|row| row.tag.iter().any(|tag| retarget(tag).is_some()))
+                .collect();
+            for mut database in database_list {
+                if !rewrite(&mut database.tag) {
+                    continue;
+                }
+                data_table
+                    .insert(&*database.hash, &database)
+                    .context("Failed to insert into DATA_TABLE")?;
+                rewritten.push(database);
+            }
+
+            let mut album_table = write_txn
+                .open_table(ALBUM_TABLE)
+                .context("Failed to open ALBUM_TABLE")?;
+            let album_list: Vec<_> = album_table
+                .iter()
+                .context("Failed to create iterator over ALBUM_TABLE")?
+                .filter_map(|entry| entry.ok())
+                .map(|(_, guard)| guard.value())
+                .filter(|row| row.tag.iter().any(|tag| retarget(tag).is_some()))
+                .collect();
+            for mut album in album_list {
+                if !rewrite(&mut album.tag) {
+                    continue;
+                }
+                album_table
+                    .insert(&*album.id, &album)
+                    .context("Failed to insert into ALBUM_TABLE")?;
+                rewritten_album_count += 1;
+            }
+        }
+        write_txn
+            .commit()
+            .context("Failed to commit write transaction")?;
+
+        info!(
+            "Rewrote tags {:?} to {:?} on {} items and {} albums",
+            from,
+            to,
+            rewritten.len(),
+            rewritten_album_count
+        );
+        Ok(rewritten)
+    }
+}
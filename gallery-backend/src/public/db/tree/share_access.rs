@@ -0,0 +1,95 @@
+use super::Tree;
+use crate::public::constant::redb::{SHARE_ACCESS_TABLE, SHARE_STATS_TABLE, SHARE_VISITOR_TABLE};
+use crate::public::structure::share_access::{ShareAccess, ShareStats};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use redb::{ReadableTable, Table, TableError, WriteTransaction};
+use std::collections::{HashMap, HashSet};
+
+impl Tree {
+    /// The latest `limit` accesses through one share, newest first.
+    pub fn read_share_access(&self, share_id: &str, limit: usize) -> Result<Vec<ShareAccess>> {
+        let read_txn = self.in_disk.begin_read()?;
+        let table = match read_txn.open_table(SHARE_ACCESS_TABLE) {
+            Ok(table) => table,
+            // No share has been opened yet.
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let access_list = table
+            .range((share_id, 0)..=(share_id, u128::MAX))?
+            .rev()
+            .take(limit)
+            .map(|entry| entry.map(|(_, guard)| guard.value()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(access_list)
+    }
+
+    /// Access counts for each of `share_id_list`.
+    pub fn read_share_stats(
+        &self,
+        share_id_list: &[ArrayString<64>],
+    ) -> Result<HashMap<ArrayString<64>, ShareStats>> {
+        let read_txn = self.in_disk.begin_read()?;
+        let table = match read_txn.open_table(SHARE_STATS_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(HashMap::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut stats_map = HashMap::new();
+        for share_id in share_id_list {
+            if let Some(guard) = table.get(share_id.as_str())? {
+                stats_map.insert(*share_id, guard.value());
+            }
+        }
+        Ok(stats_map)
+    }
+}
+
+/// Add `access` to the running totals of its share.
+pub fn count_share_access(
+    stats_table: &mut Table<&str, ShareStats>,
+    visitor_table: &mut Table<(&str, &str), u128>,
+    access: &ShareAccess,
+) -> Result<()> {
+    let share_id = access.share_id.as_str();
+    let visitor = access.ip.as_deref().unwrap_or_default();
+    let new_visitor = visitor_table.get((share_id, visitor))?.is_none();
+    if new_visitor {
+        visitor_table.insert((share_id, visitor), access.timestamp)?;
+    }
+    let mut stats = stats_table
+        .get(share_id)?
+        .map(|guard| guard.value())
+        .unwrap_or_default();
+    stats.add(access, new_visitor);
+    stats_table.insert(share_id, stats)?;
+    Ok(())
+}
+
+/// Drop the access log and totals of shares that no longer exist.
+pub fn remove_share_records(
+    txn: &WriteTransaction,
+    share_id_set: &HashSet<ArrayString<64>>,
+) -> Result<()> {
+    if share_id_set.is_empty() {
+        return Ok(());
+    }
+    let mut access_table = txn.open_table(SHARE_ACCESS_TABLE)?;
+    let mut stats_table = txn.open_table(SHARE_STATS_TABLE)?;
+    let mut visitor_table = txn.open_table(SHARE_VISITOR_TABLE)?;
+    for share_id in share_id_set {
+        access_table.retain_in(
+            (share_id.as_str(), 0)..=(share_id.as_str(), u128::MAX),
+            |_, _| false,
+        )?;
+        stats_table.remove(share_id.as_str())?;
+        // No share id sorts between `share_id` and `share_id\0`, so this covers its visitors only
+        let next_share_id = format!("{}\0", share_id);
+        visitor_table.retain_in(
+            (share_id.as_str(), "")..(next_share_id.as_str(), ""),
+            |_, _| false,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use super::ObjectStorage;
+use crate::process::io::{copy_with_retry, remove_with_retry};
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+/// Objects stored as plain files below `root` (`./object` by default).
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn create_parent(path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory tree for {:?}", parent))?;
+        }
+        Ok(())
+    }
+}
+
+impl ObjectStorage for LocalStorage {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(key);
+        Self::create_parent(&path)?;
+        fs::write(&path, bytes).with_context(|| format!("failed to write {:?}", path))
+    }
+
+    fn put_file(&self, key: &str, local: &Path) -> Result<()> {
+        let path = self.path(key);
+        if path == local {
+            return Ok(());
+        }
+        Self::create_parent(&path)?;
+        copy_with_retry(local, &path)
+            .with_context(|| format!("failed to copy file from {:?} to {:?}", local, path))?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path(key);
+        fs::read(&path).with_context(|| format!("failed to read {:?}", path))
+    }
+
+    fn get_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        let path = self.path(key);
+        let mut file = File::open(&path).with_context(|| format!("failed to open {:?}", path))?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut buffer = Vec::new();
+        file.take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn size(&self, key: &str) -> Result<u64> {
+        let path = self.path(key);
+        Ok(fs::metadata(&path)
+            .with_context(|| format!("failed to read metadata of {:?}", path))?
+            .len())
+    }
+
+    fn modified(&self, key: &str) -> Result<SystemTime> {
+        let path = self.path(key);
+        Ok(fs::metadata(&path)
+            .with_context(|| format!("failed to read metadata of {:?}", path))?
+            .modified()?)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key);
+        if !path.exists() {
+            return Ok(());
+        }
+        remove_with_retry(&path).with_context(|| format!("failed to delete {:?}", path))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.path(key).is_file())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut key_list = Vec::new();
+        for dir_entry in WalkDir::new(self.path(prefix)) {
+            let dir_entry = match dir_entry {
+                Ok(dir_entry) => dir_entry,
+                Err(err) if err.io_error().map(|e| e.kind()) == Some(ErrorKind::NotFound) => {
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            if dir_entry.file_type().is_file()
+                && let Ok(relative) = dir_entry.path().strip_prefix(&self.root)
+            {
+                key_list.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        Ok(key_list)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<()> {
+        let (from, to) = (self.path(from), self.path(to));
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&from, &to).with_context(|| format!("failed to move {:?} to {:?}", from, to))
+    }
+
+    fn open(&self, key: &str, range: Option<Range<u64>>) -> Result<Box<dyn Read + Send>> {
+        let path = self.path(key);
+        let mut file = File::open(&path).with_context(|| format!("failed to open {:?}", path))?;
+        match range {
+            Some(range) => {
+                file.seek(SeekFrom::Start(range.start))?;
+                Ok(Box::new(file.take(range.end.saturating_sub(range.start))))
+            }
+            None => Ok(Box::new(file)),
+        }
+    }
+
+    fn local_path(&self, key: &str) -> Option<PathBuf> {
+        Some(self.path(key))
+    }
+}
@@ -0,0 +1,158 @@
+use crate::public::config::{SERVER_CONFIG, StorageConfig};
+use anyhow::{Result, bail};
+use std::{
+    fs::{self, File},
+    io::{self, Cursor, Read},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+pub mod local;
+pub mod s3;
+
+/// Where originals and derivatives are kept.
+///
+/// Keys are relative object names such as `imported/ab/<hash>.jpg` or `compressed/ab/<hash>.mp4`.
+/// Implementations are blocking; call them from `spawn_blocking` or the rayon pool.
+pub trait ObjectStorage: Send + Sync {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()>;
+    fn put_file(&self, key: &str, local: &Path) -> Result<()>;
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+    fn get_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>>;
+    fn size(&self, key: &str) -> Result<u64>;
+    /// When the object was last written.
+    fn modified(&self, key: &str) -> Result<SystemTime>;
+    fn delete(&self, key: &str) -> Result<()>;
+    fn exists(&self, key: &str) -> Result<bool>;
+    /// Every key starting with `prefix`.
+    fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// A reader over `key`, or over `range` of it, that does not buffer the whole object.
+    fn open(&self, key: &str, range: Option<Range<u64>>) -> Result<Box<dyn Read + Send>> {
+        let bytes = match range {
+            Some(range) => self.get_range(key, range)?,
+            None => self.get(key)?,
+        };
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+
+    /// Move an object to another key.
+    fn rename(&self, from: &str, to: &str) -> Result<()> {
+        self.put(to, &self.get(from)?)?;
+        self.delete(from)
+    }
+
+    /// The file backing `key` when objects live on the local filesystem.
+    fn local_path(&self, _key: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// A local file with the content of `key`, downloading it into `./object/cache` if needed.
+    ///
+    /// Downloads go to a temporary file that is renamed into place once its size matches the
+    /// object, so a cached file is always complete.
+    fn fetch_to_local(&self, key: &str) -> Result<PathBuf> {
+        if let Some(path) = self.local_path(key) {
+            return Ok(path);
+        }
+        let path = Path::new(CACHE_ROOT).join(key);
+        if path.is_file() {
+            // Bump the modification time so eviction keeps recently used files.
+            File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())?;
+            return Ok(path);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let expected = self.size(key)?;
+        let temp_path = path.with_extension(format!("part-{}", uuid::Uuid::new_v4()));
+        let written = (|| -> Result<u64> {
+            let mut file = File::create(&temp_path)?;
+            let written = io::copy(&mut self.open(key, None)?, &mut file)?;
+            file.sync_all()?;
+            Ok(written)
+        })();
+        match written {
+            Ok(written) if written == expected => {}
+            Ok(written) => {
+                let _ = fs::remove_file(&temp_path);
+                bail!(
+                    "downloaded {} bytes of {} but expected {}",
+                    written,
+                    key,
+                    expected
+                );
+            }
+            Err(err) => {
+                let _ = fs::remove_file(&temp_path);
+                return Err(err);
+            }
+        }
+        fs::rename(&temp_path, &path)?;
+        evict_cache(SERVER_CONFIG.storage.cache_limit_bytes(), &path);
+        Ok(path)
+    }
+}
+
+/// Where remote objects are downloaded for local processing.
+pub const CACHE_ROOT: &str = "./object/cache";
+
+/// Delete the least recently used cached files until the cache fits in `limit` bytes.
+///
+/// `keep` is never removed, so the file just fetched survives even when it alone is over the limit.
+fn evict_cache(limit: u64, keep: &Path) {
+    let mut file_list: Vec<_> = WalkDir::new(CACHE_ROOT)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path() != keep)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.into_path()))
+        })
+        .collect();
+    let mut total: u64 = file_list.iter().map(|(_, len, _)| len).sum::<u64>()
+        + keep.metadata().map_or(0, |metadata| metadata.len());
+    if total <= limit {
+        return;
+    }
+    file_list.sort();
+    for (_, len, path) in file_list {
+        if total <= limit {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            Err(err) => warn!("Failed to evict cached file {:?}: {}", path, err),
+        }
+    }
+}
+
+pub static STORAGE: LazyLock<Box<dyn ObjectStorage>> =
+    LazyLock::new(|| match &SERVER_CONFIG.storage {
+        StorageConfig::Local => Box::new(local::LocalStorage::new("./object")),
+        StorageConfig::S3 {
+            endpoint,
+            bucket,
+            region,
+            prefix,
+            ..
+        } => Box::new(s3::S3Storage::new(endpoint, bucket, region, prefix)),
+    });
+
+/// Store a derivative that was written to its local path (e.g. by ffmpeg).
+///
+/// The local backend already holds it there; other backends upload it and drop the local file.
+pub fn commit_staged_file(key: &str, staged: &Path) -> Result<()> {
+    if STORAGE.local_path(key).as_deref() == Some(staged) {
+        return Ok(());
+    }
+    STORAGE.put_file(key, staged)?;
+    fs::remove_file(staged)?;
+    Ok(())
+}
@@ -0,0 +1,484 @@
+use super::ObjectStorage;
+use crate::public::config::PRIVATE_CONFIG;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    Method, StatusCode,
+    blocking::{Body, Client, RequestBuilder, Response},
+};
+use sha2::{Digest, Sha256};
+use std::{fs::File, io::Read, ops::Range, path::Path, time::SystemTime};
+
+/// Bodies are streamed, so requests are signed without hashing the payload.
+const PAYLOAD_HASH: &str = "UNSIGNED-PAYLOAD";
+
+/// Objects stored in an S3-compatible bucket (AWS, MinIO, Garage, ...).
+///
+/// Requests use path-style addressing and SigV4 with an unsigned payload, which every
+/// S3-compatible server accepts. Credentials come from `S3_ACCESS_KEY_ID` and
+/// `S3_SECRET_ACCESS_KEY`.
+pub struct S3Storage {
+    client: Client,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    region: String,
+    prefix: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3Storage {
+    pub fn new(endpoint: &str, bucket: &str, region: &str, prefix: &str) -> Self {
+        Self::with_credentials(
+            endpoint,
+            bucket,
+            region,
+            prefix,
+            PRIVATE_CONFIG
+                .s3_access_key_id
+                .clone()
+                .expect("S3_ACCESS_KEY_ID must be set for the S3 storage backend"),
+            PRIVATE_CONFIG
+                .s3_secret_access_key
+                .clone()
+                .expect("S3_SECRET_ACCESS_KEY must be set for the S3 storage backend"),
+        )
+    }
+
+    pub fn with_credentials(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        prefix: &str,
+        access_key_id: String,
+        secret_access_key: String,
+    ) -> Self {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Self {
+            client: Client::new(),
+            endpoint,
+            host,
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            access_key_id,
+            secret_access_key,
+        }
+    }
+
+    fn canonical_uri(&self, key: &str) -> String {
+        let object = if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.prefix, key)
+        };
+        format!(
+            "/{}/{}",
+            uri_encode(&self.bucket, false),
+            uri_encode(&object, false)
+        )
+    }
+
+    /// Build a SigV4-signed request for `key`.
+    fn request(&self, method: Method, key: &str) -> RequestBuilder {
+        self.signed_request(method, self.canonical_uri(key), Vec::new())
+    }
+
+    fn signed_request(
+        &self,
+        method: Method,
+        uri: String,
+        mut query: Vec<(&str, String)>,
+    ) -> RequestBuilder {
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name, true), uri_encode(value, true)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let (amz_date, authorization) = self.sign(&method, &uri, &canonical_query, Utc::now());
+
+        let url = if canonical_query.is_empty() {
+            format!("{}{}", self.endpoint, uri)
+        } else {
+            format!("{}{}?{}", self.endpoint, uri, canonical_query)
+        };
+        self.client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", PAYLOAD_HASH)
+            .header("authorization", authorization)
+    }
+
+    /// The `x-amz-date` and `authorization` headers for a request signed at `now`.
+    fn sign(
+        &self,
+        method: &Method,
+        uri: &str,
+        canonical_query: &str,
+        now: DateTime<Utc>,
+    ) -> (String, String) {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = PAYLOAD_HASH;
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, uri, canonical_query, self.host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [date.as_str(), self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_access_key).into_bytes(),
+                |key, part| hmac_sha256(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key_id, scope, signature
+        );
+        (amz_date, authorization)
+    }
+
+    fn send(&self, request: RequestBuilder, key: &str) -> Result<Response> {
+        let response = request
+            .send()
+            .with_context(|| format!("S3 request for {} failed", key))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            bail!("S3 request for {} returned {}: {}", key, status, body);
+        }
+        Ok(response)
+    }
+}
+
+impl ObjectStorage for S3Storage {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        self.send(self.request(Method::PUT, key).body(bytes.to_vec()), key)?;
+        Ok(())
+    }
+
+    fn put_file(&self, key: &str, local: &Path) -> Result<()> {
+        let file = File::open(local).with_context(|| format!("failed to open {:?}", local))?;
+        let length = file.metadata()?.len();
+        self.send(
+            self.request(Method::PUT, key)
+                .header("content-length", length)
+                .body(Body::sized(file, length)),
+            key,
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self.send(self.request(Method::GET, key), key)?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    fn get_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = self
+            .request(Method::GET, key)
+            .header("range", format!("bytes={}-{}", range.start, range.end - 1));
+        let response = self.send(request, key)?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    fn open(&self, key: &str, range: Option<Range<u64>>) -> Result<Box<dyn Read + Send>> {
+        let request = match range {
+            Some(range) if range.is_empty() => return Ok(Box::new(std::io::empty())),
+            Some(range) => self
+                .request(Method::GET, key)
+                .header("range", format!("bytes={}-{}", range.start, range.end - 1)),
+            None => self.request(Method::GET, key),
+        };
+        Ok(Box::new(self.send(request, key)?))
+    }
+
+    fn size(&self, key: &str) -> Result<u64> {
+        let response = self.send(self.request(Method::HEAD, key), key)?;
+        response
+            .headers()
+            .get("content-length")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .with_context(|| format!("S3 HEAD for {} returned no content length", key))
+    }
+
+    fn modified(&self, key: &str) -> Result<SystemTime> {
+        let response = self.send(self.request(Method::HEAD, key), key)?;
+        let last_modified = response
+            .headers()
+            .get("last-modified")
+            .and_then(|value| value.to_str().ok())
+            .with_context(|| format!("S3 HEAD for {} returned no last modified time", key))?;
+        Ok(DateTime::parse_from_rfc2822(last_modified)
+            .with_context(|| format!("invalid last modified time {:?}", last_modified))?
+            .into())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.send(self.request(Method::DELETE, key), key)?;
+        Ok(())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let full_prefix = if self.prefix.is_empty() {
+            prefix.to_string()
+        } else {
+            format!("{}/{}", self.prefix, prefix)
+        };
+        let strip = full_prefix.len() - prefix.len();
+        let uri = format!("/{}", uri_encode(&self.bucket, false));
+
+        let mut key_list = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type", "2".to_string()),
+                ("prefix", full_prefix.clone()),
+            ];
+            if let Some(token) = continuation_token.take() {
+                query.push(("continuation-token", token));
+            }
+            let response =
+                self.send(self.signed_request(Method::GET, uri.clone(), query), prefix)?;
+            let xml = response.text()?;
+            key_list.extend(
+                xml_values(&xml, "Key")
+                    .into_iter()
+                    .map(|key| xml_unescape(key)[strip..].to_string()),
+            );
+            match xml_values(&xml, "NextContinuationToken").first() {
+                Some(token) if xml_values(&xml, "IsTruncated").first() == Some(&"true") => {
+                    continuation_token = Some(xml_unescape(token));
+                }
+                _ => break,
+            }
+        }
+        Ok(key_list)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        let response = self
+            .request(Method::HEAD, key)
+            .send()
+            .with_context(|| format!("S3 request for {} failed", key))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => bail!("S3 HEAD for {} returned {}", key, status),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything except unreserved characters (and `/` in paths), as SigV4 requires.
+fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The text of every `<tag>...</tag>` element in a ListObjectsV2 response.
+fn xml_values<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    xml.split(open.as_str())
+        .skip(1)
+        .filter_map(|rest| rest.split_once(close.as_str()).map(|(value, _)| value))
+        .collect()
+}
+
+/// Decode the five predefined entities and numeric character references; anything else is kept
+/// as is.
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let character = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            character.map(|character| (character, end))
+        });
+        match decoded {
+            Some((character, end)) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn storage() -> S3Storage {
+        S3Storage::with_credentials(
+            "http://127.0.0.1:9000/",
+            "photos",
+            "us-east-1",
+            "/backup/",
+            "AKIDEXAMPLE".to_string(),
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        )
+    }
+
+    fn signature(authorization: &str) -> &str {
+        authorization.rsplit_once("Signature=").unwrap().1
+    }
+
+    #[test]
+    fn canonical_uri_keeps_slashes_and_encodes_the_rest() {
+        assert_eq!(
+            storage().canonical_uri("imported/ab/ab c.jpg"),
+            "/photos/backup/imported/ab/ab%20c.jpg"
+        );
+    }
+
+    #[test]
+    fn signs_object_request() {
+        let storage = storage();
+        let now = Utc.with_ymd_and_hms(2013, 5, 24, 0, 0, 0).unwrap();
+        let uri = storage.canonical_uri("imported/ab/ab c.jpg");
+        let (amz_date, authorization) = storage.sign(&Method::GET, &uri, "", now);
+        assert_eq!(amz_date, "20130524T000000Z");
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20130524/us-east-1/s3/aws4_request, "
+        ));
+        assert_eq!(
+            signature(&authorization),
+            "929f10fda896b8a5706595ca8b5c2da9b784a2c047cfc1db15615a033f3ccb11"
+        );
+    }
+
+    #[test]
+    fn signs_list_query() {
+        let now = Utc.with_ymd_and_hms(2013, 5, 24, 0, 0, 0).unwrap();
+        let (_, authorization) = storage().sign(
+            &Method::GET,
+            "/photos",
+            "list-type=2&prefix=backup%2Fimported%2F",
+            now,
+        );
+        assert_eq!(
+            signature(&authorization),
+            "33a9864b5d18b2bfcbd1b251baf0421823865bdebcf865fd43364f3238ee760e"
+        );
+    }
+
+    #[test]
+    fn reads_list_response() {
+        let xml = "<ListBucketResult><IsTruncated>true</IsTruncated>\
+            <Contents><Key>a&amp;b.jpg</Key></Contents><Contents><Key>c.jpg</Key></Contents>\
+            <NextContinuationToken>token</NextContinuationToken></ListBucketResult>";
+        assert_eq!(xml_values(xml, "Key"), ["a&amp;b.jpg", "c.jpg"]);
+        assert_eq!(xml_values(xml, "NextContinuationToken"), ["token"]);
+
+        let xml = "<ListBucketResult><Contents><Key>it&#39;s &quot;a&quot; &lt;b&gt; &amp; \
+            &apos;c&apos; &#34;d&#x22; &#X41;.jpg</Key></Contents></ListBucketResult>";
+        let key_list: Vec<_> = xml_values(xml, "Key")
+            .into_iter()
+            .map(xml_unescape)
+            .collect();
+        assert_eq!(key_list, [r#"it's "a" <b> & 'c' "d" A.jpg"#]);
+        assert_eq!(
+            xml_unescape("a & b &unknown; &#xZZ;"),
+            "a & b &unknown; &#xZZ;"
+        );
+    }
+
+    /// Round trip against a real server, e.g.
+    /// `S3_TEST_ENDPOINT=http://127.0.0.1:9000 S3_TEST_BUCKET=test cargo test -- --ignored`
+    /// with MinIO's default `minioadmin` credentials.
+    #[test]
+    #[ignore]
+    fn minio_round_trip() {
+        let endpoint = std::env::var("S3_TEST_ENDPOINT").unwrap();
+        let bucket = std::env::var("S3_TEST_BUCKET").unwrap();
+        let credential = |name: &str| std::env::var(name).unwrap_or("minioadmin".to_string());
+        let storage = S3Storage::with_credentials(
+            &endpoint,
+            &bucket,
+            "us-east-1",
+            "urocissa-test",
+            credential("S3_ACCESS_KEY_ID"),
+            credential("S3_SECRET_ACCESS_KEY"),
+        );
+        let key = "imported/00/round trip+1.txt";
+        storage.put(key, b"hello world").unwrap();
+        assert!(storage.exists(key).unwrap());
+        assert_eq!(storage.size(key).unwrap(), 11);
+        assert_eq!(storage.get_range(key, 6..11).unwrap(), b"world");
+        let mut streamed = Vec::new();
+        storage
+            .open(key, None)
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, b"hello world");
+        assert!(
+            storage
+                .list("imported/00/")
+                .unwrap()
+                .contains(&key.to_string())
+        );
+        storage.delete(key).unwrap();
+        assert!(!storage.exists(key).unwrap());
+    }
+}
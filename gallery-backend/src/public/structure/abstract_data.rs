@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use arrayvec::ArrayString;
 use serde::{Deserialize, Serialize};
@@ -27,13 +27,13 @@ impl AbstractData {
     pub fn width(self: &Self) -> u32 {
         match self {
             AbstractData::Database(database) => database.width,
-            AbstractData::Album(_) => 300,
+            AbstractData::Album(album) => album.cover_width_height().0,
         }
     }
     pub fn height(self: &Self) -> u32 {
         match self {
             AbstractData::Database(database) => database.height,
-            AbstractData::Album(_) => 300,
+            AbstractData::Album(album) => album.cover_width_height().1,
         }
     }
     pub fn tag(self: &Self) -> &HashSet<String> {
@@ -48,6 +48,12 @@ impl AbstractData {
             AbstractData::Album(album) => &mut album.tag,
         }
     }
+    pub fn user_defined_metadata_mut(self: &mut Self) -> &mut HashMap<String, Vec<String>> {
+        match self {
+            AbstractData::Database(database) => &mut database.user_defined_metadata,
+            AbstractData::Album(album) => &mut album.user_defined_metadata,
+        }
+    }
 }
 
 impl From<Database> for AbstractData {
@@ -61,4 +67,3 @@ impl From<Album> for AbstractData {
         AbstractData::Album(album)
     }
 }
-
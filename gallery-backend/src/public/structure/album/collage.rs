@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use arrayvec::ArrayString;
+use bitcode::{Decode, Encode};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use crate::operations::indexation::generate_image_hash::generate_thumbhash;
+use crate::operations::open_db::open_data_table;
+use crate::public::storage::STORAGE;
+use crate::public::structure::database_struct::database::definition::Database;
+
+use super::Album;
+
+/// Side of one tile of a collage, in pixels.
+const TILE_SIZE: u32 = 300;
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+pub enum CollageLayout {
+    #[default]
+    #[serde(rename = "2x2")]
+    Grid2x2,
+    #[serde(rename = "3x1")]
+    Row3x1,
+}
+
+impl CollageLayout {
+    /// `(columns, rows)`.
+    pub fn grid(self) -> (u32, u32) {
+        match self {
+            CollageLayout::Grid2x2 => (2, 2),
+            CollageLayout::Row3x1 => (3, 1),
+        }
+    }
+    pub fn slot_count(self) -> usize {
+        let (columns, rows) = self.grid();
+        (columns * rows) as usize
+    }
+    pub fn width_height(self) -> (u32, u32) {
+        let (columns, rows) = self.grid();
+        (columns * TILE_SIZE, rows * TILE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CollageCover {
+    pub layout: CollageLayout,
+    /// Items picked by hand; remaining slots are filled with favorites, then the earliest items.
+    pub pinned: Vec<ArrayString<64>>,
+    /// Items the collage shows, in slot order.
+    pub hash_list: Vec<ArrayString<64>>,
+    /// Id of the generated JPEG at `compressed/<xx>/<image>.jpg`; `None` until it is rendered.
+    pub image: Option<ArrayString<64>>,
+    pub thumbhash: Vec<u8>,
+}
+
+impl CollageCover {
+    pub fn new(layout: CollageLayout, pinned: Vec<ArrayString<64>>) -> Self {
+        Self {
+            layout,
+            pinned,
+            ..Default::default()
+        }
+    }
+
+    pub fn image_key(image: &str) -> String {
+        format!("compressed/{}/{}.jpg", &image[0..2], image)
+    }
+
+    /// Derived from the album, layout and items, so an unchanged collage is never redrawn and
+    /// no two albums share an image.
+    fn expected_image(&self, album_id: &str) -> ArrayString<64> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(format!("collage:{}:{:?}", album_id, self.layout).as_bytes());
+        for hash in &self.hash_list {
+            hasher.update(hash.as_bytes());
+        }
+        hasher.finalize().to_hex()
+    }
+
+    /// Choose the items to show from the album's members, sorted by timestamp ascending.
+    pub fn pick(&mut self, data_in_album: &[(&Database, u128)]) {
+        let slot_count = self.layout.slot_count();
+        let mut hash_list: Vec<ArrayString<64>> = self
+            .pinned
+            .iter()
+            .filter(|hash| data_in_album.iter().any(|(db, _)| db.hash == **hash))
+            .copied()
+            .collect();
+        let favorites = data_in_album.iter().filter(|(db, _)| db.favorite);
+        let rest = data_in_album.iter().filter(|(db, _)| !db.favorite);
+        for (database, _) in favorites.chain(rest) {
+            if hash_list.len() >= slot_count {
+                break;
+            }
+            if !hash_list.contains(&database.hash) {
+                hash_list.push(database.hash);
+            }
+        }
+        hash_list.truncate(slot_count);
+        self.hash_list = hash_list;
+    }
+}
+
+impl Album {
+    /// The hash whose compressed JPEG is the cover: the collage if one is rendered, else `cover`.
+    pub fn cover_image(&self) -> Option<ArrayString<64>> {
+        self.collage
+            .as_ref()
+            .and_then(|collage| collage.image)
+            .or(self.cover)
+    }
+
+    pub fn cover_width_height(&self) -> (u32, u32) {
+        match &self.collage {
+            Some(collage) if collage.image.is_some() => collage.layout.width_height(),
+            _ => (300, 300),
+        }
+    }
+
+    /// Redraw the collage if its items changed since it was last rendered.
+    ///
+    /// Blocking, so call it outside any write transaction. Nothing is deleted here; the row that
+    /// ends up committed is settled by [`Album::adopt_collage`].
+    pub fn render_collage(&mut self) -> Result<()> {
+        let Some(collage) = &mut self.collage else {
+            return Ok(());
+        };
+        if collage.hash_list.is_empty() {
+            collage.image = None;
+            collage.thumbhash.clear();
+            return Ok(());
+        }
+        let expected_image = collage.expected_image(&self.id);
+        if collage.image == Some(expected_image) {
+            return Ok(());
+        }
+        let canvas = draw_collage(collage.layout, &collage.hash_list)?;
+        let mut jpeg_bytes = Vec::new();
+        canvas
+            .write_to(&mut Cursor::new(&mut jpeg_bytes), ImageFormat::Jpeg)
+            .context("failed to encode collage JPEG")?;
+        let key = CollageCover::image_key(&expected_image);
+        STORAGE
+            .put(&key, &jpeg_bytes)
+            .context(format!("failed to save collage to {}", key))?;
+        collage.image = Some(expected_image);
+        collage.thumbhash = generate_thumbhash(&DynamicImage::ImageRgb8(canvas));
+        self.thumbhash = Some(collage.thumbhash.clone());
+        Ok(())
+    }
+
+    /// Take over the collage drawn by [`Album::render_collage`] on `rendered`, an earlier copy of
+    /// this album, if it still shows the same items.
+    ///
+    /// Returns the keys of images no longer referenced, to delete once this row is committed.
+    pub fn adopt_collage(&mut self, rendered: Album) -> Vec<String> {
+        let old_image = self.collage.as_ref().and_then(|collage| collage.image);
+        let rendered_image = rendered.collage.as_ref().and_then(|collage| collage.image);
+        if let (Some(collage), Some(rendered_collage)) = (&mut self.collage, rendered.collage)
+            && collage.layout == rendered_collage.layout
+            && collage.hash_list == rendered_collage.hash_list
+        {
+            collage.image = rendered_collage.image;
+            collage.thumbhash = rendered_collage.thumbhash;
+            if collage.image.is_some() {
+                self.thumbhash = rendered.thumbhash;
+            }
+        }
+        let image = self.collage.as_ref().and_then(|collage| collage.image);
+        let mut stale_key_list: Vec<String> = [old_image, rendered_image]
+            .into_iter()
+            .flatten()
+            .filter(|stale_image| Some(*stale_image) != image)
+            .map(|stale_image| CollageCover::image_key(&stale_image))
+            .collect();
+        stale_key_list.dedup();
+        stale_key_list
+    }
+}
+
+/// Lay the items' thumbnails out in the grid, cycling through them if there are fewer than slots.
+fn draw_collage(layout: CollageLayout, hash_list: &[ArrayString<64>]) -> Result<RgbImage> {
+    let data_table = open_data_table()?;
+    let mut tile_list = Vec::with_capacity(hash_list.len());
+    for hash in hash_list {
+        let Some(database) = data_table.get(&**hash)?.map(|guard| guard.value()) else {
+            continue;
+        };
+        let bytes = STORAGE.get(&database.thumbnail_key()).context(format!(
+            "failed to read thumbnail into memory: {}",
+            database.thumbnail_key()
+        ))?;
+        let tile = image::load_from_memory(&bytes)
+            .context(format!("failed to decode thumbnail: {}", database.hash))?
+            .resize_to_fill(TILE_SIZE, TILE_SIZE, FilterType::Triangle)
+            .to_rgb8();
+        tile_list.push(tile);
+    }
+    if tile_list.is_empty() {
+        anyhow::bail!("no thumbnails to draw the collage from");
+    }
+
+    let (columns, _) = layout.grid();
+    let (width, height) = layout.width_height();
+    let mut canvas = RgbImage::new(width, height);
+    for slot in 0..layout.slot_count() {
+        let tile = &tile_list[slot % tile_list.len()];
+        let x = (slot as u32 % columns) * TILE_SIZE;
+        let y = (slot as u32 / columns) * TILE_SIZE;
+        image::imageops::replace(&mut canvas, tile, x as i64, y as i64);
+    }
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> ArrayString<64> {
+        ArrayString::from(name).unwrap()
+    }
+
+    fn album_with_collage(hash_list: &[&str], image: Option<&str>) -> Album {
+        let mut collage = CollageCover::new(CollageLayout::Grid2x2, Vec::new());
+        collage.hash_list = hash_list.iter().map(|hash| id(hash)).collect();
+        collage.image = image.map(id);
+        Album {
+            id: id("album"),
+            collage: Some(collage),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adopt_replaces_image_and_returns_old_key() {
+        let mut album = album_with_collage(&["a", "b"], Some("old0"));
+        let rendered = album_with_collage(&["a", "b"], Some("new0"));
+        let stale_key_list = album.adopt_collage(rendered);
+        assert_eq!(album.collage.unwrap().image, Some(id("new0")));
+        assert_eq!(stale_key_list, vec![CollageCover::image_key("old0")]);
+    }
+
+    #[test]
+    fn adopt_skips_collage_changed_meanwhile() {
+        let mut album = album_with_collage(&["a", "c"], Some("old0"));
+        let rendered = album_with_collage(&["a", "b"], Some("new0"));
+        let stale_key_list = album.adopt_collage(rendered);
+        assert_eq!(album.collage.unwrap().image, Some(id("old0")));
+        assert_eq!(stale_key_list, vec![CollageCover::image_key("new0")]);
+    }
+
+    #[test]
+    fn adopt_unchanged_collage_deletes_nothing() {
+        let mut album = album_with_collage(&["a"], Some("same"));
+        let rendered = album_with_collage(&["a"], Some("same"));
+        assert!(album.adopt_collage(rendered).is_empty());
+    }
+}
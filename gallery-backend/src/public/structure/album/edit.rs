@@ -1,8 +1,10 @@
+use std::collections::HashSet;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::public::db::tree::TREE;
+use crate::public::db::tree::album_tree::album_subtree_in;
 use crate::public::structure::{
     abstract_data::AbstractData, database_struct::database::definition::Database,
 };
@@ -12,7 +14,14 @@ use super::Album;
 impl Album {
     pub fn set_cover(&mut self, cover_data: &Database) {
         self.cover = Some(cover_data.hash);
-        self.thumbhash = Some(cover_data.thumbhash.clone());
+        // A rendered collage keeps its own thumbhash
+        if self
+            .collage
+            .as_ref()
+            .is_none_or(|collage| collage.image.is_none())
+        {
+            self.thumbhash = Some(cover_data.thumbhash.clone());
+        }
         self.width = cover_data.width;
         self.height = cover_data.height;
     }
@@ -21,13 +30,25 @@ impl Album {
         // Acquire a read lock on the in-memory tree
         let ref_data = TREE.in_memory.read().unwrap();
 
+        let member_album_ids = if self.include_children {
+            album_subtree_in(&ref_data, self.id)
+        } else {
+            HashSet::from([self.id])
+        };
+
         // Collect relevant Database entries along with their timestamps
         let mut data_in_album: Vec<(&Database, u128)> = ref_data
             .par_iter()
             .filter_map(
                 |database_timestamp| match &database_timestamp.abstract_data {
                     AbstractData::Database(database) => {
-                        if database.album.contains(&self.id) {
+                        // Trashed items stay members but do not count towards the album
+                        if database
+                            .album
+                            .iter()
+                            .any(|album_id| member_album_ids.contains(album_id))
+                            && !database.is_trashed()
+                        {
                             Some((database, database_timestamp.timestamp))
                         } else {
                             None
@@ -52,9 +73,20 @@ impl Album {
             self.thumbhash = None;
             self.width = 0;
             self.height = 0;
+            self.custom_order.clear();
+            if let Some(collage) = &mut self.collage {
+                collage.hash_list.clear();
+            }
             return;
         }
 
+        // Drop items that have left the album from the manual order
+        if !self.custom_order.is_empty() {
+            let member_hashes: HashSet<_> = data_in_album.iter().map(|(db, _)| db.hash).collect();
+            self.custom_order
+                .retain(|hash| member_hashes.contains(hash));
+        }
+
         // Sort the data by timestamp to determine start and end times
         data_in_album.sort_unstable_by_key(|&(_, timestamp)| timestamp);
 
@@ -83,6 +115,10 @@ impl Album {
             self.start_time = Some(*first_timestamp);
         }
 
+        if let Some(collage) = &mut self.collage {
+            collage.pick(&data_in_album);
+        }
+
         // Set the end_time using the last (latest) timestamp
         if let Some((_, last_timestamp)) = data_in_album.last() {
             self.end_time = Some(*last_timestamp);
@@ -4,19 +4,41 @@ use arrayvec::ArrayString;
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
+pub mod collage;
 pub mod edit;
 pub mod new;
+pub mod share_limit;
+pub mod share_password;
+
+pub use collage::{CollageCover, CollageLayout};
 
 #[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct Share {
     pub url: ArrayString<64>,
     pub description: String,
+    /// Argon2 hash of the password viewers must enter; see [`Share::set_password`].
     pub password: Option<String>,
     pub show_metadata: bool,
     pub show_download: bool,
     pub show_upload: bool,
+    /// Unix time in seconds after which the link stops working; `0` never expires.
     pub exp: u64,
+    /// Unix time in seconds.
+    #[serde(default)]
+    pub created_at: u64,
+    /// Times the link may be opened; `None` is unlimited.
+    #[serde(default)]
+    pub max_views: Option<u64>,
+    #[serde(default)]
+    pub view_count: u64,
+    /// Also share the albums nested below the shared one.
+    #[serde(default)]
+    pub include_children: bool,
+    /// Bumped by [`Share::set_password`] so that tokens issued for an earlier password stop
+    /// working.
+    #[serde(default)]
+    pub password_version: u32,
 }
 
 #[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq, Hash)]
@@ -32,6 +54,10 @@ pub struct ResolvedShare {
 pub struct Album {
     pub id: ArrayString<64>,
     pub title: Option<String>,
+    /// Folder this album is filed under.
+    pub parent_id: Option<ArrayString<64>>,
+    /// Count items of nested albums towards this one as well.
+    pub include_children: bool,
     pub created_time: u128,
     pub start_time: Option<u128>,
     pub end_time: Option<u128>,
@@ -46,4 +72,26 @@ pub struct Album {
     pub item_count: usize,
     pub item_size: u64,
     pub pending: bool,
+    pub favorite: bool,
+    pub archived: bool,
+    pub hidden: bool,
+    /// When the album was moved to the trash; purged once the retention period has passed.
+    pub trashed_at: Option<u128>,
+    pub sort_mode: AlbumSortMode,
+    /// Item hashes in the order set by hand; used when `sort_mode` is `Custom`.
+    pub custom_order: Vec<ArrayString<64>>,
+    /// Generated cover made of several items; shown instead of `cover` when set.
+    pub collage: Option<CollageCover>,
+}
+
+/// How the items of an album are ordered when it is opened on its own.
+#[derive(Debug, Clone, Copy, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlbumSortMode {
+    /// Newest first, like the main timeline.
+    #[default]
+    DateDesc,
+    DateAsc,
+    /// `custom_order` first, then any items not placed yet, newest first.
+    Custom,
 }
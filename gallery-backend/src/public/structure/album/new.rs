@@ -5,7 +5,7 @@ use std::{
 
 use arrayvec::ArrayString;
 
-use super::{Album, ResolvedShare, Share};
+use super::{Album, AlbumSortMode, ResolvedShare, Share};
 
 impl Album {
     pub fn new(id: ArrayString<64>, title: Option<String>) -> Self {
@@ -16,6 +16,8 @@ impl Album {
         Self {
             id: id,
             title: title,
+            parent_id: None,
+            include_children: false,
             created_time: timestamp,
             cover: None,
             thumbhash: None,
@@ -30,12 +32,21 @@ impl Album {
             item_count: 0,
             item_size: 0,
             pending: false,
+            favorite: false,
+            archived: false,
+            hidden: false,
+            trashed_at: None,
+            sort_mode: AlbumSortMode::DateDesc,
+            custom_order: Vec::new(),
+            collage: None,
         }
     }
 }
 
 impl ResolvedShare {
-    pub fn new(album_id: ArrayString<64>, album_title: Option<String>, share: Share) -> Self {
+    pub fn new(album_id: ArrayString<64>, album_title: Option<String>, mut share: Share) -> Self {
+        // Sent to viewers of the share; they have no use for the password hash
+        share.password = None;
         Self {
             share,
             album_id,
@@ -0,0 +1,137 @@
+use anyhow::{Result, anyhow};
+use arrayvec::ArrayString;
+use redb::ReadableTable;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::public::constant::redb::ALBUM_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::db::tree::share_access::remove_share_records;
+
+use super::Share;
+
+impl Share {
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.exp != 0 && now >= self.exp
+    }
+
+    /// Every allowed view has been used; the last viewer can still load what they opened.
+    pub fn is_exhausted(&self) -> bool {
+        self.max_views
+            .is_some_and(|max_views| self.view_count >= max_views)
+    }
+
+    /// Viewers need a token from `/post/authenticate_share`, which checks the password and counts
+    /// the view once for the whole visit.
+    pub fn needs_token(&self) -> bool {
+        self.has_password() || self.max_views.is_some()
+    }
+}
+
+/// Count one opening of a share link; `false` once `max_views` is used up.
+pub fn record_share_view(album_id: ArrayString<64>, share_id: ArrayString<64>) -> Result<bool> {
+    let txn = TREE.in_disk.begin_write()?;
+    {
+        let mut album_table = txn.open_table(ALBUM_TABLE)?;
+        let mut album = album_table
+            .get(&*album_id)?
+            .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?
+            .value();
+        let share = album
+            .share_list
+            .get_mut(&share_id)
+            .ok_or_else(|| anyhow!("Share '{}' not found in album '{}'", share_id, album_id))?;
+        if share.is_exhausted() {
+            return Ok(false);
+        }
+        share.view_count += 1;
+        album_table.insert(&*album_id, album)?;
+    }
+    txn.commit()?;
+    Ok(true)
+}
+
+/// Drop the shares `should_remove` picks from every album, along with their access log, in one
+/// transaction; returns how many.
+pub fn remove_shares(should_remove: impl Fn(ArrayString<64>, &Share) -> bool) -> Result<usize> {
+    let txn = TREE.in_disk.begin_write()?;
+    let mut removed_id_set = HashSet::new();
+    {
+        let mut album_table = txn.open_table(ALBUM_TABLE)?;
+        let album_list: Vec<_> = album_table
+            .iter()?
+            .filter_map(|entry| entry.ok())
+            .map(|(_, guard)| guard.value())
+            .collect();
+        for mut album in album_list {
+            let before = removed_id_set.len();
+            album.share_list.retain(|share_id, share| {
+                let remove = should_remove(album.id, share);
+                if remove {
+                    removed_id_set.insert(*share_id);
+                }
+                !remove
+            });
+            if removed_id_set.len() != before {
+                album_table.insert(&*album.id, &album)?;
+            }
+        }
+    }
+    remove_share_records(&txn, &removed_id_set)?;
+    txn.commit()?;
+    Ok(removed_id_set.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn expiry_is_an_absolute_time() {
+        let share = |exp| Share {
+            exp,
+            ..Default::default()
+        };
+        assert!(!share(0).is_expired());
+        assert!(!share(now() + 3600).is_expired());
+        assert!(share(now() - 1).is_expired());
+        assert!(share(1).is_expired());
+    }
+
+    #[test]
+    fn exhausted_once_views_reach_the_limit() {
+        let share = |max_views, view_count| Share {
+            max_views,
+            view_count,
+            ..Default::default()
+        };
+        assert!(!share(None, 1000).is_exhausted());
+        assert!(!share(Some(3), 2).is_exhausted());
+        assert!(share(Some(3), 3).is_exhausted());
+        assert!(share(Some(0), 0).is_exhausted());
+    }
+
+    #[test]
+    fn limited_or_protected_shares_need_a_token() {
+        assert!(!Share::default().needs_token());
+        let limited = Share {
+            max_views: Some(1),
+            ..Default::default()
+        };
+        assert!(limited.needs_token());
+        let mut protected = Share::default();
+        protected.set_password(Some("secret")).unwrap();
+        assert!(protected.needs_token());
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{Result, anyhow};
+use argon2::Argon2;
+use argon2::password_hash::{
+    PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng,
+};
+
+use super::Share;
+
+impl Share {
+    /// Store `password` as an argon2 hash; `None` or an empty string removes it.
+    ///
+    /// Share tokens issued before the change are revoked.
+    pub fn set_password(&mut self, password: Option<&str>) -> Result<()> {
+        self.password = match password.filter(|password| !password.is_empty()) {
+            Some(password) => Some(hash_password(password)?),
+            None => None,
+        };
+        self.password_version = self.password_version.wrapping_add(1);
+        Ok(())
+    }
+
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+
+    pub fn verify_password(&self, password: &str) -> bool {
+        match &self.password {
+            None => true,
+            Some(stored) => PasswordHash::new(stored).is_ok_and(|parsed| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed)
+                    .is_ok()
+            }),
+        }
+    }
+
+    /// Hash a password stored in plain text by shares created before passwords were hashed.
+    ///
+    /// Returns whether the share changed.
+    pub fn migrate_plaintext_password(&mut self) -> Result<bool> {
+        match &self.password {
+            Some(stored) if PasswordHash::new(stored).is_err() => {
+                self.password = Some(hash_password(stored)?);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!("Failed to hash share password: {}", err))?;
+    Ok(hash.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_password_verifies() {
+        let mut share = Share::default();
+        share.set_password(Some("secret")).unwrap();
+        assert_ne!(share.password.as_deref(), Some("secret"));
+        assert!(share.verify_password("secret"));
+        assert!(!share.verify_password("wrong"));
+        assert!(!share.verify_password(""));
+    }
+
+    #[test]
+    fn empty_password_removes_it() {
+        let mut share = Share::default();
+        share.set_password(Some("secret")).unwrap();
+        share.set_password(Some("")).unwrap();
+        assert!(!share.has_password());
+        assert!(share.verify_password("anything"));
+    }
+
+    #[test]
+    fn changing_password_bumps_version() {
+        let mut share = Share::default();
+        share.set_password(Some("secret")).unwrap();
+        let version = share.password_version;
+        share.set_password(Some("secret")).unwrap();
+        assert_ne!(share.password_version, version);
+        let version = share.password_version;
+        share.set_password(None).unwrap();
+        assert_ne!(share.password_version, version);
+    }
+
+    #[test]
+    fn plaintext_password_is_rejected_until_migrated() {
+        let mut share = Share {
+            password: Some("secret".to_string()),
+            ..Default::default()
+        };
+        assert!(!share.verify_password("secret"));
+
+        assert!(share.migrate_plaintext_password().unwrap());
+        assert!(share.verify_password("secret"));
+        assert!(!share.migrate_plaintext_password().unwrap());
+    }
+}
@@ -3,7 +3,7 @@ use arrayvec::ArrayString;
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
 pub struct Database {
@@ -20,4 +20,33 @@ pub struct Database {
     pub alias: Vec<FileModify>,
     pub ext_type: String,
     pub pending: bool,
+    pub storage: StorageMode,
+    /// Star rating from `xmp:Rating`, `0` when unrated.
+    pub rating: u8,
+    /// Colour label from `xmp:Label`.
+    pub label: Option<String>,
+    /// Caption from `dc:description` or IPTC, falling back to the title.
+    pub description: Option<String>,
+    /// When the rating, label or caption was last edited in Urocissa, in milliseconds. Metadata
+    /// files last modified before then do not override those edits on reindex.
+    pub metadata_edited_at: Option<u128>,
+    /// Free-form annotations, like [`Album::user_defined_metadata`](crate::public::structure::album::Album::user_defined_metadata).
+    pub user_defined_metadata: HashMap<String, Vec<String>>,
+    pub favorite: bool,
+    pub archived: bool,
+    /// Never shown to share viewers.
+    pub hidden: bool,
+    /// When the item was moved to the trash; purged once the retention period has passed.
+    pub trashed_at: Option<u128>,
+}
+
+/// Where the original file of an item is kept.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageMode {
+    /// Copied into `./object/imported`.
+    #[default]
+    Imported,
+    /// Left where it was found and read from its source aliases.
+    Reference,
 }
@@ -1,4 +1,5 @@
-use super::definition::Database;
+use super::definition::{Database, StorageMode};
+use crate::public::storage::STORAGE;
 use std::path::PathBuf;
 
 impl Database {
@@ -23,10 +24,96 @@ impl Database {
             format!("./object/compressed/{}/{}.mp4", &self.hash[0..2], self.hash)
         }
     }
+    /// Object keys in `STORAGE`; the local backend maps them to the paths above.
+    pub fn imported_key(&self) -> String {
+        format!("imported/{}/{}.{}", &self.hash[0..2], self.hash, self.ext)
+    }
+    pub fn compressed_key(&self) -> String {
+        if self.ext_type == "image" {
+            format!("compressed/{}/{}.jpg", &self.hash[0..2], self.hash)
+        } else {
+            format!("compressed/{}/{}.mp4", &self.hash[0..2], self.hash)
+        }
+    }
+    pub fn thumbnail_key(&self) -> String {
+        format!("compressed/{}/{}.jpg", &self.hash[0..2], self.hash)
+    }
+    /// Derivatives the item should have; a pending video has no compressed mp4 yet.
+    pub fn derivative_keys(&self) -> Vec<String> {
+        if self.ext_type == "video" {
+            let mut key_list = vec![self.thumbnail_key()];
+            if !self.pending {
+                key_list.push(self.compressed_key());
+            }
+            key_list
+        } else {
+            vec![self.compressed_key()]
+        }
+    }
+    /// Every object the item owns in `STORAGE`.
+    pub fn object_keys(&self) -> Vec<String> {
+        let mut key_list = self.derivative_keys();
+        if self.storage == StorageMode::Imported {
+            key_list.push(self.imported_key());
+        }
+        key_list
+    }
     pub fn imported_path(&self) -> PathBuf {
         PathBuf::from(self.imported_path_string())
     }
 
+    /// The file holding the original bytes.
+    ///
+    /// For reference-only items this is the newest live alias that still exists, falling back
+    /// over older ones; `None` when every source is gone.
+    pub fn original_path_opt(&self) -> Option<PathBuf> {
+        match self.storage {
+            StorageMode::Imported => match STORAGE.local_path(&self.imported_key()) {
+                Some(path) => Some(path),
+                // Remote backend: prefer an unchanged source that is still around over
+                // downloading the object.
+                None => self
+                    .unchanged_source_path_opt()
+                    .or_else(|| STORAGE.fetch_to_local(&self.imported_key()).ok()),
+            },
+            StorageMode::Reference => self.live_source_path_opt(),
+        }
+    }
+    /// The newest alias that is not stale and still exists.
+    pub fn live_source_path_opt(&self) -> Option<PathBuf> {
+        let mut alias_list: Vec<_> = self
+            .alias
+            .iter()
+            .filter(|file_modify| !file_modify.is_stale())
+            .collect();
+        alias_list.sort_by(|a, b| b.cmp(a));
+        alias_list
+            .into_iter()
+            .map(|file_modify| PathBuf::from(&file_modify.file))
+            .find(|path| path.is_file())
+    }
+    /// The newest live alias whose size and modification time still match the index.
+    pub fn unchanged_source_path_opt(&self) -> Option<PathBuf> {
+        let mut alias_list: Vec<_> = self
+            .alias
+            .iter()
+            .filter(|file_modify| !file_modify.is_stale())
+            .collect();
+        alias_list.sort_by(|a, b| b.cmp(a));
+        alias_list
+            .into_iter()
+            .find(|file_modify| file_modify.is_unchanged(self.size))
+            .map(|file_modify| PathBuf::from(&file_modify.file))
+    }
+    /// Like `original_path_opt`, but falls back to the imported path so errors still name a file.
+    pub fn original_path(&self) -> PathBuf {
+        self.original_path_opt()
+            .unwrap_or_else(|| self.imported_path())
+    }
+    pub fn original_path_string(&self) -> String {
+        self.original_path().to_string_lossy().into_owned()
+    }
+
     pub fn compressed_path(&self) -> PathBuf {
         PathBuf::from(self.compressed_path_string())
     }
@@ -5,9 +5,9 @@ use crate::{
 
 use rand::Rng;
 
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use super::definition::Database;
+use super::definition::{Database, StorageMode};
 
 impl Database {
     pub fn generate_random_data() -> Self {
@@ -32,8 +32,19 @@ impl Database {
                 file: String::from("/"),
                 modified: 0,
                 scan_time: 0,
+                stale_since: None,
             }],
             pending: false,
+            storage: StorageMode::Imported,
+            rating: 0,
+            label: None,
+            description: None,
+            metadata_edited_at: None,
+            user_defined_metadata: HashMap::new(),
+            favorite: false,
+            archived: false,
+            hidden: false,
+            trashed_at: None,
         }
     }
 }
@@ -3,3 +3,5 @@ pub mod generate_path;
 pub mod generate_random_data;
 pub mod generate_timestamp;
 pub mod new;
+pub mod trash;
+pub mod update_alias;
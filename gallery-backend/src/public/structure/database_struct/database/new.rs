@@ -1,12 +1,15 @@
 use crate::{
     public::constant::VALID_IMAGE_EXTENSIONS,
-    public::structure::database_struct::{database::definition::Database, file_modify::FileModify},
+    public::structure::database_struct::{
+        database::definition::{Database, StorageMode},
+        file_modify::FileModify,
+    },
 };
 use anyhow::Context;
 use anyhow::Result;
 use arrayvec::ArrayString;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fs::metadata,
     path::Path,
     time::UNIX_EPOCH,
@@ -46,6 +49,16 @@ impl Database {
             album: HashSet::new(),
             alias: vec![file_modify],
             pending: false,
+            storage: StorageMode::Imported,
+            rating: 0,
+            label: None,
+            description: None,
+            metadata_edited_at: None,
+            user_defined_metadata: HashMap::new(),
+            favorite: false,
+            archived: false,
+            hidden: false,
+            trashed_at: None,
         })
     }
 
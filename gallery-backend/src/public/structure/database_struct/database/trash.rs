@@ -0,0 +1,7 @@
+use super::definition::Database;
+
+impl Database {
+    pub fn is_trashed(&self) -> bool {
+        self.trashed_at.is_some()
+    }
+}
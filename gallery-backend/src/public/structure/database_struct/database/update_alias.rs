@@ -0,0 +1,56 @@
+use super::definition::Database;
+use crate::public::config::{PRIVATE_CONFIG, SERVER_CONFIG};
+use path_clean::PathClean;
+use std::path::{Path, PathBuf};
+
+impl Database {
+    /// Marks every alias at or below `path` as stale. Returns whether anything changed.
+    pub fn mark_alias_stale(&mut self, path: &Path, now: u128) -> bool {
+        let mut changed = false;
+        for file_modify in &mut self.alias {
+            if !file_modify.is_stale() && Path::new(&file_modify.file).starts_with(path) {
+                file_modify.stale_since = Some(now);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Rewrites aliases at or below `from` to live under `to` instead.
+    ///
+    /// Returns the new paths of the moved aliases.
+    pub fn move_alias(&mut self, from: &Path, to: &Path) -> Vec<PathBuf> {
+        let mut moved = Vec::new();
+        for file_modify in &mut self.alias {
+            let new_path = match Path::new(&file_modify.file).strip_prefix(from) {
+                Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(rest) => to.join(rest).clean(),
+                Err(_) => continue,
+            };
+            file_modify.file = new_path.to_string_lossy().into_owned();
+            file_modify.stale_since = None;
+            moved.push(new_path);
+        }
+        moved
+    }
+
+    /// Adds or removes `sync.missingSourceTag` depending on whether any synced source file is left.
+    pub fn refresh_missing_source_tag(&mut self) {
+        let Some(tag) = &SERVER_CONFIG.sync.missing_source_tag else {
+            return;
+        };
+        let any_stale = self.alias.iter().any(|file_modify| file_modify.is_stale());
+        let any_live_source = self.alias.iter().any(|file_modify| {
+            !file_modify.is_stale()
+                && PRIVATE_CONFIG
+                    .sync_path
+                    .iter()
+                    .any(|sync_path| Path::new(&file_modify.file).starts_with(sync_path))
+        });
+        if any_stale && !any_live_source {
+            self.tag.insert(tag.clone());
+        } else {
+            self.tag.remove(tag);
+        }
+    }
+}
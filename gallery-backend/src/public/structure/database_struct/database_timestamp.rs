@@ -42,7 +42,7 @@ impl DataBaseTimestampReturn {
                 ClaimsHash::new(database.hash, token_timestamp, allow_original).encode()
             }
             AbstractData::Album(album) => {
-                if let Some(cover_hash) = album.cover {
+                if let Some(cover_hash) = album.cover_image() {
                     ClaimsHash::new(cover_hash, token_timestamp, allow_original).encode()
                 } else {
                     String::new()
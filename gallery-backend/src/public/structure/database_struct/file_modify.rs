@@ -2,7 +2,7 @@ use bitcode::{Decode, Encode};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
-use std::{cmp::Ordering, path::Path};
+use std::{cmp::Ordering, path::Path, time::UNIX_EPOCH};
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, Decode, Encode, Hash)]
 #[serde(rename_all = "camelCase")]
@@ -10,6 +10,8 @@ pub struct FileModify {
     pub file: String,
     pub modified: u128,
     pub scan_time: u128,
+    /// Set when the file was deleted or moved away from this path.
+    pub stale_since: Option<u128>,
 }
 #[derive(Debug, Default, Clone, Deserialize, Serialize, Decode, Encode, PartialEq, Eq, Hash)]
 pub struct FileModifySize {
@@ -23,8 +25,26 @@ impl FileModify {
             file: file.to_string_lossy().into_owned(),
             modified,
             scan_time: Utc::now().timestamp_millis() as u128,
+            stale_since: None,
         }
     }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale_since.is_some()
+    }
+
+    /// Whether the file still has the size and modification time it had when indexed.
+    pub fn is_unchanged(&self, size: u64) -> bool {
+        let Ok(metadata) = Path::new(&self.file).metadata() else {
+            return false;
+        };
+        metadata.len() == size
+            && metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .is_some_and(|modified| modified.as_millis() == self.modified)
+    }
 }
 
 impl PartialEq for FileModify {
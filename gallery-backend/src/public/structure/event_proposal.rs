@@ -0,0 +1,28 @@
+use arrayvec::ArrayString;
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// A run of items close together in time (and place, where known) that could become an album.
+#[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventProposal {
+    /// Hash of the earliest item, so the proposal keeps its id while later items join it.
+    pub id: ArrayString<64>,
+    pub title: String,
+    pub start_time: u128,
+    pub end_time: u128,
+    pub hash_list: Vec<ArrayString<64>>,
+    /// Centroid of the items that carry GPS coordinates.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub status: ProposalStatus,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProposalStatus {
+    #[default]
+    Proposed,
+    /// Kept so that the same event is not proposed again.
+    Rejected,
+}
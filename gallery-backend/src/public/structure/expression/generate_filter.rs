@@ -1,35 +1,74 @@
 use super::Expression;
+use crate::operations::indexation::generate_xmp::keyword_to_tag;
+use crate::public::db::tree::TREE;
 use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::flag::Flag;
+use crate::public::structure::tag_tree::tag_is_within;
 
 impl Expression {
     pub fn generate_filter(self) -> Box<dyn Fn(&AbstractData) -> bool + Sync + Send> {
         match self {
+            // Children are built once, not per item: `AlbumTree` resolves its subtree when built
             Expression::Or(expressions) => {
-                let filters: Vec<Expression> = expressions;
+                let filters: Vec<_> = expressions
+                    .into_iter()
+                    .map(Expression::generate_filter)
+                    .collect();
                 Box::new(move |abstract_data: &AbstractData| {
-                    filters.iter().any(|expr| {
-                        let filter = expr.clone().generate_filter();
-                        filter(abstract_data)
-                    })
+                    filters.iter().any(|filter| filter(abstract_data))
                 })
             }
             Expression::And(expressions) => {
-                let filters: Vec<Expression> = expressions;
+                let filters: Vec<_> = expressions
+                    .into_iter()
+                    .map(Expression::generate_filter)
+                    .collect();
                 Box::new(move |abstract_data: &AbstractData| {
-                    filters.iter().all(|expr| {
-                        let filter = expr.clone().generate_filter();
-                        filter(abstract_data)
-                    })
+                    filters.iter().all(|filter| filter(abstract_data))
                 })
             }
             Expression::Not(expression) => {
                 let inner_filter = expression.clone().generate_filter();
                 Box::new(move |abstract_data: &AbstractData| !inner_filter(abstract_data))
             }
-            Expression::Tag(tag) => {
+            Expression::Tag(tag) => match Flag::from_tag(&tag) {
+                // Older clients still query flags as `_favorite`-style tags
+                Some(flag) => {
+                    Box::new(move |abstract_data: &AbstractData| abstract_data.has_flag(flag))
+                }
+                // A hierarchical tag also matches everything below it
+                None => Box::new(move |abstract_data: &AbstractData| {
+                    abstract_data
+                        .tag()
+                        .iter()
+                        .any(|candidate| tag_is_within(candidate, &tag))
+                }),
+            },
+            Expression::Favorite => {
+                Box::new(|abstract_data: &AbstractData| abstract_data.has_flag(Flag::Favorite))
+            }
+            Expression::Archived => {
+                Box::new(|abstract_data: &AbstractData| abstract_data.has_flag(Flag::Archived))
+            }
+            Expression::Hidden => {
+                Box::new(|abstract_data: &AbstractData| abstract_data.has_flag(Flag::Hidden))
+            }
+            Expression::Trashed => {
+                Box::new(|abstract_data: &AbstractData| abstract_data.has_flag(Flag::Trashed))
+            }
+            Expression::Rating(rating) => {
+                Box::new(move |abstract_data: &AbstractData| match abstract_data {
+                    AbstractData::Database(db) => db.rating >= rating,
+                    AbstractData::Album(_) => false,
+                })
+            }
+            Expression::Label(label) => {
                 Box::new(move |abstract_data: &AbstractData| match abstract_data {
-                    AbstractData::Database(db) => db.tag.contains(&tag),
-                    AbstractData::Album(album) => album.tag.contains(&tag),
+                    AbstractData::Database(db) => db
+                        .label
+                        .as_ref()
+                        .is_some_and(|db_label| db_label.eq_ignore_ascii_case(&label)),
+                    AbstractData::Album(_) => false,
                 })
             }
             Expression::ExtType(ext_type) => {
@@ -67,11 +106,47 @@ impl Expression {
                     AbstractData::Album(_) => false,
                 })
             }
+            Expression::LongerThan(seconds) => {
+                Box::new(move |abstract_data: &AbstractData| match abstract_data {
+                    AbstractData::Database(db) => db
+                        .exif_vec
+                        .get("duration")
+                        .and_then(|duration| duration.parse::<f64>().ok())
+                        .is_some_and(|duration| duration > seconds as f64),
+                    AbstractData::Album(_) => false,
+                })
+            }
+            Expression::Description(description) => {
+                let description_lower = description.to_ascii_lowercase();
+                Box::new(move |abstract_data: &AbstractData| match abstract_data {
+                    AbstractData::Database(db) => db
+                        .description
+                        .as_ref()
+                        .is_some_and(|text| text.to_ascii_lowercase().contains(&description_lower)),
+                    AbstractData::Album(_) => false,
+                })
+            }
+            Expression::Metadata(key, value) => {
+                let value_lower = value.to_ascii_lowercase();
+                Box::new(move |abstract_data: &AbstractData| {
+                    let metadata = match abstract_data {
+                        AbstractData::Database(db) => &db.user_defined_metadata,
+                        AbstractData::Album(album) => &album.user_defined_metadata,
+                    };
+                    metadata.get(&key).is_some_and(|values| {
+                        value_lower.is_empty()
+                            || values
+                                .iter()
+                                .any(|text| text.to_ascii_lowercase().contains(&value_lower))
+                    })
+                })
+            }
             Expression::Path(path) => {
                 let path_lower = path.to_ascii_lowercase();
                 Box::new(move |abstract_data: &AbstractData| match abstract_data {
                     AbstractData::Database(db) => db.alias.iter().any(|file_modify| {
-                        file_modify.file.to_ascii_lowercase().contains(&path_lower)
+                        !file_modify.is_stale()
+                            && file_modify.file.to_ascii_lowercase().contains(&path_lower)
                     }),
                     AbstractData::Album(_) => false,
                 })
@@ -82,27 +157,59 @@ impl Expression {
                     AbstractData::Album(_) => false,
                 })
             }
-            Expression::Any(any_identifier) => {
-                let any_lower = any_identifier.to_ascii_lowercase();
+            // Takes the in-memory tree lock, so build filters before holding it
+            Expression::AlbumTree(album_id) => {
+                let subtree = TREE.album_subtree(album_id);
                 Box::new(move |abstract_data: &AbstractData| match abstract_data {
                     AbstractData::Database(db) => {
-                        db.tag.contains(&any_identifier)
-                            || db.ext_type.contains(&any_identifier)
-                            || db.ext.to_ascii_lowercase().contains(&any_lower)
-                            || db.exif_vec.get("Make").map_or(false, |make_of_exif| {
-                                make_of_exif.to_ascii_lowercase().contains(&any_lower)
-                            })
-                            || db.exif_vec.get("Model").map_or(false, |model_of_exif| {
-                                model_of_exif.to_ascii_lowercase().contains(&any_lower)
-                            })
-                            || db.alias.iter().any(|file_modify| {
-                                file_modify.file.to_ascii_lowercase().contains(&any_lower)
-                            })
-                    }
-                    AbstractData::Album(album) => {
-                        album.tag.contains(&any_identifier)
-                            || "album".to_ascii_lowercase().contains(&any_lower)
+                        db.album.iter().any(|album_id| subtree.contains(album_id))
                     }
+                    AbstractData::Album(_) => false,
+                })
+            }
+            Expression::Any(any_identifier) => {
+                let any_lower = any_identifier.to_ascii_lowercase();
+                let flag_opt = Flag::from_tag(&any_identifier);
+                // Also find keywords imported under `metadata.keywordTagPrefix`
+                let keyword_tag = keyword_to_tag(&any_identifier);
+                Box::new(move |abstract_data: &AbstractData| {
+                    flag_opt.is_some_and(|flag| abstract_data.has_flag(flag))
+                        || match abstract_data {
+                            AbstractData::Database(db) => {
+                                db.tag.contains(&any_identifier)
+                                    || db.tag.contains(&keyword_tag)
+                                    || db.label.as_ref().is_some_and(|label| {
+                                        label.eq_ignore_ascii_case(&any_identifier)
+                                    })
+                                    || db.description.as_ref().is_some_and(|description| {
+                                        description.to_ascii_lowercase().contains(&any_lower)
+                                    })
+                                    || db
+                                        .user_defined_metadata
+                                        .values()
+                                        .flatten()
+                                        .any(|text| text.to_ascii_lowercase().contains(&any_lower))
+                                    || db.ext_type.contains(&any_identifier)
+                                    || db.ext.to_ascii_lowercase().contains(&any_lower)
+                                    || db.exif_vec.get("Make").map_or(false, |make_of_exif| {
+                                        make_of_exif.to_ascii_lowercase().contains(&any_lower)
+                                    })
+                                    || db.exif_vec.get("Model").map_or(false, |model_of_exif| {
+                                        model_of_exif.to_ascii_lowercase().contains(&any_lower)
+                                    })
+                                    || db.alias.iter().any(|file_modify| {
+                                        !file_modify.is_stale()
+                                            && file_modify
+                                                .file
+                                                .to_ascii_lowercase()
+                                                .contains(&any_lower)
+                                    })
+                            }
+                            AbstractData::Album(album) => {
+                                album.tag.contains(&any_identifier)
+                                    || "album".to_ascii_lowercase().contains(&any_lower)
+                            }
+                        }
                 })
             }
         }
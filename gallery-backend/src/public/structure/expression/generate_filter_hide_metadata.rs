@@ -1,5 +1,7 @@
 use super::Expression;
+use crate::public::db::tree::TREE;
 use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::flag::Flag;
 use arrayvec::ArrayString;
 
 impl Expression {
@@ -9,24 +11,18 @@ impl Expression {
     ) -> Box<dyn Fn(&AbstractData) -> bool + Send + Sync> {
         match self {
             Expression::Or(exprs) => {
-                let id = shared_album_id.clone();
-                let filters = exprs;
-                Box::new(move |data| {
-                    filters.iter().any(|expr| {
-                        let filter = expr.clone().generate_filter_hide_metadata(id.clone());
-                        filter(data)
-                    })
-                })
+                let filters: Vec<_> = exprs
+                    .into_iter()
+                    .map(|expr| expr.generate_filter_hide_metadata(shared_album_id))
+                    .collect();
+                Box::new(move |data| filters.iter().any(|filter| filter(data)))
             }
             Expression::And(exprs) => {
-                let id = shared_album_id.clone();
-                let filters = exprs;
-                Box::new(move |data| {
-                    filters.iter().all(|expr| {
-                        let filter = expr.clone().generate_filter_hide_metadata(id.clone());
-                        filter(data)
-                    })
-                })
+                let filters: Vec<_> = exprs
+                    .into_iter()
+                    .map(|expr| expr.generate_filter_hide_metadata(shared_album_id))
+                    .collect();
+                Box::new(move |data| filters.iter().all(|filter| filter(data)))
             }
             Expression::Not(expr) => {
                 let inner = expr.generate_filter_hide_metadata(shared_album_id);
@@ -36,8 +32,14 @@ impl Expression {
             /* ---------- Allowed album condition ---------- */
             Expression::Album(album_id) => {
                 if album_id == shared_album_id {
+                    // Archived, hidden and trashed items are never shown to share viewers
                     Box::new(move |data| match data {
-                        AbstractData::Database(db) => db.album.contains(&album_id),
+                        AbstractData::Database(db) => {
+                            db.album.contains(&album_id)
+                                && !db.archived
+                                && !db.hidden
+                                && !db.is_trashed()
+                        }
                         AbstractData::Album(_) => false,
                     })
                 } else {
@@ -46,8 +48,38 @@ impl Expression {
                 }
             }
 
+            Expression::AlbumTree(album_id) => {
+                if album_id == shared_album_id {
+                    // Only used for shares with `include_children`; takes the in-memory tree
+                    // lock, so build filters before holding it
+                    let subtree = TREE.album_subtree(album_id);
+                    Box::new(move |data| match data {
+                        AbstractData::Database(db) => {
+                            db.album.iter().any(|album_id| subtree.contains(album_id))
+                                && !db.archived
+                                && !db.hidden
+                                && !db.is_trashed()
+                        }
+                        AbstractData::Album(_) => false,
+                    })
+                } else {
+                    Box::new(|_| false)
+                }
+            }
+
             /* ---------- Supplementary conditions that must be invalid ---------- */
-            Expression::Tag(_) | Expression::Path(_) => Box::new(|_| false),
+            Expression::Tag(_)
+            | Expression::Path(_)
+            | Expression::Favorite
+            | Expression::Rating(_)
+            | Expression::Label(_)
+            | Expression::Description(_)
+            | Expression::Metadata(_, _) => Box::new(|_| false),
+
+            /* ---------- Flags that decide visibility ---------- */
+            Expression::Archived => Box::new(|data| data.has_flag(Flag::Archived)),
+            Expression::Hidden => Box::new(|data| data.has_flag(Flag::Hidden)),
+            Expression::Trashed => Box::new(|data| data.has_flag(Flag::Trashed)),
 
             /* ---------- Still allowed embedded / file-related conditions ---------- */
             Expression::ExtType(ext_type) => Box::new(move |data| match data {
@@ -81,6 +113,14 @@ impl Expression {
                     AbstractData::Album(_) => false,
                 })
             }
+            Expression::LongerThan(seconds) => Box::new(move |data| match data {
+                AbstractData::Database(db) => db
+                    .exif_vec
+                    .get("duration")
+                    .and_then(|duration| duration.parse::<f64>().ok())
+                    .is_some_and(|duration| duration > seconds as f64),
+                AbstractData::Album(_) => false,
+            }),
 
             /* ---------- Any: removes tag / alias / album / path matching ---------- */
             Expression::Any(identifier) => {
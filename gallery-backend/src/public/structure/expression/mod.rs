@@ -8,6 +8,10 @@ pub enum Expression {
     Or(Vec<Expression>),
     And(Vec<Expression>),
     Not(Box<Expression>),
+    /// The tag or any tag below it, so `places` also matches `places/japan`.
+    ///
+    /// Saved queries and `autoTag` rules written before tags were hierarchical now match the
+    /// whole subtree too, which only makes a difference where tags below the queried one exist.
     Tag(String),
     ExtType(String),
     Ext(String),
@@ -15,5 +19,22 @@ pub enum Expression {
     Make(String),
     Path(String),
     Album(ArrayString<64>),
+    /// Items in the album or any album nested below it.
+    AlbumTree(ArrayString<64>),
     Any(String),
+    Favorite,
+    Archived,
+    Hidden,
+    Trashed,
+    /// Rated at least this many stars.
+    Rating(u8),
+    /// Colour label, compared case-insensitively.
+    Label(String),
+    /// Caption contains the text, case-insensitively.
+    Description(String),
+    /// `user_defined_metadata` has the key with a value containing the text, case-insensitively;
+    /// an empty text only checks for the key.
+    Metadata(String, String),
+    /// Videos running longer than this many seconds.
+    LongerThan(u64),
 }
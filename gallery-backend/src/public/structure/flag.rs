@@ -0,0 +1,112 @@
+use super::abstract_data::AbstractData;
+
+/// Built-in markers on items and albums.
+///
+/// They are stored as typed fields, but the frontend still reads and writes them as the
+/// `_favorite`-style tags returned by [`Flag::tag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Favorite,
+    Archived,
+    Hidden,
+    Trashed,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 4] = [Flag::Favorite, Flag::Archived, Flag::Hidden, Flag::Trashed];
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Flag::Favorite => "_favorite",
+            Flag::Archived => "_archived",
+            Flag::Hidden => "_hidden",
+            Flag::Trashed => "_trashed",
+        }
+    }
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|flag| flag.tag() == tag)
+    }
+}
+
+impl AbstractData {
+    pub fn has_flag(&self, flag: Flag) -> bool {
+        match (self, flag) {
+            (AbstractData::Database(database), Flag::Favorite) => database.favorite,
+            (AbstractData::Database(database), Flag::Archived) => database.archived,
+            (AbstractData::Database(database), Flag::Hidden) => database.hidden,
+            (AbstractData::Database(database), Flag::Trashed) => database.trashed_at.is_some(),
+            (AbstractData::Album(album), Flag::Favorite) => album.favorite,
+            (AbstractData::Album(album), Flag::Archived) => album.archived,
+            (AbstractData::Album(album), Flag::Hidden) => album.hidden,
+            (AbstractData::Album(album), Flag::Trashed) => album.trashed_at.is_some(),
+        }
+    }
+
+    /// `now` becomes the trash time when moving into the trash.
+    pub fn set_flag(&mut self, flag: Flag, on: bool, now: u128) {
+        let (favorite, archived, hidden, trashed_at) = match self {
+            AbstractData::Database(database) => (
+                &mut database.favorite,
+                &mut database.archived,
+                &mut database.hidden,
+                &mut database.trashed_at,
+            ),
+            AbstractData::Album(album) => (
+                &mut album.favorite,
+                &mut album.archived,
+                &mut album.hidden,
+                &mut album.trashed_at,
+            ),
+        };
+        match flag {
+            Flag::Favorite => *favorite = on,
+            Flag::Archived => *archived = on,
+            Flag::Hidden => *hidden = on,
+            Flag::Trashed if on => {
+                trashed_at.get_or_insert(now);
+            }
+            Flag::Trashed => *trashed_at = None,
+        }
+    }
+
+    pub fn trashed_at(&self) -> Option<u128> {
+        match self {
+            AbstractData::Database(database) => database.trashed_at,
+            AbstractData::Album(album) => album.trashed_at,
+        }
+    }
+
+    /// Add or remove a tag as sent by the frontend, routing `_favorite`-style tags to their flag.
+    pub fn edit_tag(&mut self, tag: &str, add: bool, now: u128) {
+        match Flag::from_tag(tag) {
+            Some(flag) => self.set_flag(flag, add, now),
+            None if add => {
+                self.tag_mut().insert(tag.to_string());
+            }
+            None => {
+                self.tag_mut().remove(tag);
+            }
+        }
+    }
+
+    /// Turn `_favorite`-style entries left in the tag set into flags; returns whether any were found.
+    pub fn migrate_flag_tags(&mut self, now: u128) -> bool {
+        let mut migrated = false;
+        for flag in Flag::ALL {
+            if self.tag_mut().remove(flag.tag()) {
+                self.set_flag(flag, true, now);
+                migrated = true;
+            }
+        }
+        migrated
+    }
+
+    /// Put the flags back into the tag set for clients that still read them from there.
+    pub fn expose_flag_tags(&mut self) {
+        for flag in Flag::ALL {
+            if self.has_flag(flag) {
+                self.tag_mut().insert(flag.tag().to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use arrayvec::ArrayString;
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+
+/// The indexing stage a job is currently executing.
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, Decode, Encode, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStage {
+    Open,
+    Hash,
+    Dedup,
+    Copy,
+    Index,
+    Video,
+}
+
+/// A file submitted for indexing, persisted so that it survives a restart.
+///
+/// Jobs are keyed by their (cleaned) source path and removed once the file is fully processed.
+#[derive(Debug, Clone, Deserialize, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub path: String,
+    pub hash: Option<ArrayString<64>>,
+    pub presigned_album_id_opt: Option<ArrayString<64>>,
+    pub stage: JobStage,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// When the next retry is due; `None` while the job is running or once it has given up.
+    pub retry_at: Option<u64>,
+    pub created_time: u64,
+    pub updated_time: u64,
+}
+
+impl Job {
+    pub fn new(path: String, presigned_album_id_opt: Option<ArrayString<64>>) -> Self {
+        let now = get_current_timestamp_u64();
+        Self {
+            path,
+            hash: None,
+            presigned_album_id_opt,
+            stage: JobStage::Open,
+            attempts: 0,
+            last_error: None,
+            retry_at: None,
+            created_time: now,
+            updated_time: now,
+        }
+    }
+
+    /// A job that only has the video transcode left to do.
+    pub fn new_video(path: String, hash: ArrayString<64>) -> Self {
+        Self {
+            hash: Some(hash),
+            stage: JobStage::Video,
+            ..Self::new(path, None)
+        }
+    }
+}
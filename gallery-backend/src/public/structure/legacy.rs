@@ -0,0 +1,143 @@
+//! Structs exactly as they were encoded by v0.16.
+//!
+//! bitcode is not self-describing, so a row written before a field was added no longer decodes
+//! as the current struct. The `Value` impls fall back to these and convert.
+
+use arrayvec::ArrayString;
+use bitcode::Decode;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::album::{Album, Share};
+use super::database_struct::{database::definition::Database, file_modify::FileModify};
+
+#[derive(Decode)]
+pub struct FileModifyV16 {
+    pub file: String,
+    pub modified: u128,
+    pub scan_time: u128,
+}
+
+impl From<FileModifyV16> for FileModify {
+    fn from(legacy: FileModifyV16) -> Self {
+        Self {
+            file: legacy.file,
+            modified: legacy.modified,
+            scan_time: legacy.scan_time,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Decode)]
+pub struct DatabaseV16 {
+    pub hash: ArrayString<64>,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    pub thumbhash: Vec<u8>,
+    pub phash: Vec<u8>,
+    pub ext: String,
+    pub exif_vec: BTreeMap<String, String>,
+    pub tag: HashSet<String>,
+    pub album: HashSet<ArrayString<64>>,
+    pub alias: Vec<FileModifyV16>,
+    pub ext_type: String,
+    pub pending: bool,
+}
+
+impl From<DatabaseV16> for Database {
+    fn from(legacy: DatabaseV16) -> Self {
+        Self {
+            hash: legacy.hash,
+            size: legacy.size,
+            width: legacy.width,
+            height: legacy.height,
+            thumbhash: legacy.thumbhash,
+            phash: legacy.phash,
+            ext: legacy.ext,
+            exif_vec: legacy.exif_vec,
+            tag: legacy.tag,
+            album: legacy.album,
+            alias: legacy.alias.into_iter().map(FileModify::from).collect(),
+            ext_type: legacy.ext_type,
+            pending: legacy.pending,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Decode)]
+pub struct ShareV16 {
+    pub url: ArrayString<64>,
+    pub description: String,
+    pub password: Option<String>,
+    pub show_metadata: bool,
+    pub show_download: bool,
+    pub show_upload: bool,
+    pub exp: u64,
+}
+
+impl From<ShareV16> for Share {
+    fn from(legacy: ShareV16) -> Self {
+        Self {
+            url: legacy.url,
+            description: legacy.description,
+            password: legacy.password,
+            show_metadata: legacy.show_metadata,
+            show_download: legacy.show_download,
+            show_upload: legacy.show_upload,
+            // v0.16 stored the creation time here and never expired links
+            exp: 0,
+            created_at: legacy.exp,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Decode)]
+pub struct AlbumV16 {
+    pub id: ArrayString<64>,
+    pub title: Option<String>,
+    pub created_time: u128,
+    pub start_time: Option<u128>,
+    pub end_time: Option<u128>,
+    pub last_modified_time: u128,
+    pub cover: Option<ArrayString<64>>,
+    pub thumbhash: Option<Vec<u8>>,
+    pub user_defined_metadata: HashMap<String, Vec<String>>,
+    pub share_list: HashMap<ArrayString<64>, ShareV16>,
+    pub tag: HashSet<String>,
+    pub width: u32,
+    pub height: u32,
+    pub item_count: usize,
+    pub item_size: u64,
+    pub pending: bool,
+}
+
+impl From<AlbumV16> for Album {
+    fn from(legacy: AlbumV16) -> Self {
+        Self {
+            id: legacy.id,
+            title: legacy.title,
+            created_time: legacy.created_time,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            last_modified_time: legacy.last_modified_time,
+            cover: legacy.cover,
+            thumbhash: legacy.thumbhash,
+            user_defined_metadata: legacy.user_defined_metadata,
+            share_list: legacy
+                .share_list
+                .into_iter()
+                .map(|(share_id, share)| (share_id, Share::from(share)))
+                .collect(),
+            tag: legacy.tag,
+            width: legacy.width,
+            height: legacy.height,
+            item_count: legacy.item_count,
+            item_size: legacy.item_size,
+            pending: legacy.pending,
+            ..Default::default()
+        }
+    }
+}
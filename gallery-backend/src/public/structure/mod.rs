@@ -1,7 +1,13 @@
 pub mod abstract_data;
 pub mod album;
 pub mod database_struct;
+pub mod event_proposal;
 pub mod expression;
+pub mod flag;
 pub mod guard;
+pub mod job;
+pub mod legacy;
 pub mod reduced_data;
 pub mod row;
+pub mod share_access;
+pub mod tag_tree;
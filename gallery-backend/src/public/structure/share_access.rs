@@ -0,0 +1,88 @@
+use arrayvec::ArrayString;
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// One request made through a share link.
+#[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareAccess {
+    pub album_id: ArrayString<64>,
+    pub share_id: ArrayString<64>,
+    /// Unix time in milliseconds.
+    pub timestamp: u128,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub kind: ShareAccessKind,
+    /// The item viewed or downloaded.
+    pub hash: Option<ArrayString<64>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShareAccessKind {
+    /// The shared album was opened.
+    #[default]
+    Open,
+    /// A thumbnail or preview was loaded.
+    View,
+    /// An original was fetched.
+    Download,
+}
+
+/// Access counts for one share, as listed with the albums.
+///
+/// Totals since the share was created; pruning old log entries does not lower them.
+#[derive(Debug, Clone, Deserialize, Default, Serialize, Decode, Encode, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareStats {
+    pub open_count: u64,
+    pub view_count: u64,
+    pub download_count: u64,
+    /// Distinct client addresses.
+    pub visitor_count: usize,
+    pub last_access: Option<u128>,
+}
+
+impl ShareStats {
+    /// Count one access; `new_visitor` when its address was not seen through this share before.
+    pub fn add(&mut self, access: &ShareAccess, new_visitor: bool) {
+        match access.kind {
+            ShareAccessKind::Open => self.open_count += 1,
+            ShareAccessKind::View => self.view_count += 1,
+            ShareAccessKind::Download => self.download_count += 1,
+        }
+        if new_visitor {
+            self.visitor_count += 1;
+        }
+        self.last_access = self.last_access.max(Some(access.timestamp));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_count_kinds_visitors_and_last_access() {
+        let access = |kind, timestamp| ShareAccess {
+            kind,
+            timestamp,
+            ..Default::default()
+        };
+        let mut stats = ShareStats::default();
+        stats.add(&access(ShareAccessKind::Open, 20), true);
+        stats.add(&access(ShareAccessKind::View, 10), false);
+        stats.add(&access(ShareAccessKind::View, 30), true);
+        stats.add(&access(ShareAccessKind::Download, 25), false);
+        assert_eq!(
+            stats,
+            ShareStats {
+                open_count: 1,
+                view_count: 2,
+                download_count: 1,
+                visitor_count: 2,
+                last_access: Some(30),
+            }
+        );
+    }
+}
@@ -0,0 +1,81 @@
+/// Separates the levels of a hierarchical tag such as `places/japan/kyoto`.
+pub const TAG_SEPARATOR: char = '/';
+
+/// Whether `tag` is `node` itself or lies anywhere below it.
+pub fn tag_is_within(tag: &str, node: &str) -> bool {
+    tag.strip_prefix(node)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(TAG_SEPARATOR))
+}
+
+/// `places/japan/kyoto` yields `places` and `places/japan`.
+pub fn tag_ancestors(tag: &str) -> impl Iterator<Item = &str> {
+    tag.match_indices(TAG_SEPARATOR)
+        .map(move |(index, _)| &tag[..index])
+        .filter(|ancestor| !ancestor.is_empty())
+}
+
+pub fn parent_tag(tag: &str) -> Option<&str> {
+    tag.rsplit_once(TAG_SEPARATOR)
+        .map(|(parent, _)| parent)
+        .filter(|parent| !parent.is_empty())
+}
+
+/// Move `tag` from below `from` to below `to`; `None` when it is outside `from`.
+///
+/// Also `None` when `to` lies below `from`: `a` to `a/b` would turn an existing `a/b` into
+/// `a/b/b`, so such a move is not well defined.
+pub fn retarget_tag(tag: &str, from: &str, to: &str) -> Option<String> {
+    if to != from && tag_is_within(to, from) {
+        return None;
+    }
+    tag_is_within(tag, from).then(|| format!("{}{}", to, &tag[from.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_respects_separator() {
+        assert!(tag_is_within("places", "places"));
+        assert!(tag_is_within("places/japan/kyoto", "places"));
+        assert!(!tag_is_within("placesholder", "places"));
+        assert!(!tag_is_within("places", "places/japan"));
+    }
+
+    #[test]
+    fn ancestors_and_parent() {
+        assert_eq!(
+            tag_ancestors("places/japan/kyoto").collect::<Vec<_>>(),
+            ["places", "places/japan"]
+        );
+        assert_eq!(tag_ancestors("/leading").count(), 0);
+        assert_eq!(parent_tag("places/japan/kyoto"), Some("places/japan"));
+        assert_eq!(parent_tag("places"), None);
+    }
+
+    #[test]
+    fn retarget_moves_subtree() {
+        assert_eq!(
+            retarget_tag("places/japan/kyoto", "places/japan", "trips").as_deref(),
+            Some("trips/kyoto")
+        );
+        assert_eq!(
+            retarget_tag("places/japan", "places/japan", "trips").as_deref(),
+            Some("trips")
+        );
+        assert_eq!(retarget_tag("a/b/c", "a/b", "a").as_deref(), Some("a/c"));
+        assert_eq!(
+            retarget_tag("places/japanese", "places/japan", "trips"),
+            None
+        );
+    }
+
+    #[test]
+    fn retarget_into_own_subtree_is_rejected() {
+        assert_eq!(retarget_tag("a/b", "a", "a/b"), None);
+        assert_eq!(retarget_tag("a", "a", "a/b"), None);
+        // A sibling that merely shares the prefix is fine
+        assert_eq!(retarget_tag("a", "a", "ab").as_deref(), Some("ab"));
+    }
+}
@@ -9,7 +9,7 @@ use std::{
     mem,
     sync::{
         Arc, LazyLock, OnceLock,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     time::Instant,
 };
@@ -181,6 +181,13 @@ pub struct Dashboard {
     handled: AtomicU64,
     pending: AtomicU64,
     total_duration: AtomicF64,
+    scanning: AtomicBool,
+    scan_checked: AtomicU64,
+    scan_queued: AtomicU64,
+    verifying: AtomicBool,
+    verify_total: AtomicU64,
+    verify_checked: AtomicU64,
+    verify_issues: AtomicU64,
 }
 
 pub static LOGGER_TX: OnceLock<UnboundedSender<String>> = OnceLock::new();
@@ -194,6 +201,13 @@ impl Dashboard {
             handled: AtomicU64::new(0),
             pending: AtomicU64::new(0),
             total_duration: AtomicF64::new(0.0),
+            scanning: AtomicBool::new(false),
+            scan_checked: AtomicU64::new(0),
+            scan_queued: AtomicU64::new(0),
+            verifying: AtomicBool::new(false),
+            verify_total: AtomicU64::new(0),
+            verify_checked: AtomicU64::new(0),
+            verify_issues: AtomicU64::new(0),
         }
     }
 
@@ -272,6 +286,39 @@ impl Dashboard {
         self.pending.fetch_sub(1, Ordering::Relaxed);
     }
 
+    /// Reset the reconciliation counters and show them in the stats line
+    pub fn start_scan(&self) {
+        self.scan_checked.store(0, Ordering::Relaxed);
+        self.scan_queued.store(0, Ordering::Relaxed);
+        self.scanning.store(true, Ordering::Relaxed);
+    }
+    pub fn scan_checked(&self, queued: bool) {
+        self.scan_checked.fetch_add(1, Ordering::Relaxed);
+        if queued {
+            self.scan_queued.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    pub fn finish_scan(&self) {
+        self.scanning.store(false, Ordering::Relaxed);
+    }
+
+    /// Reset the integrity verification counters and show them in the stats line
+    pub fn start_verify(&self, total: u64) {
+        self.verify_total.store(total, Ordering::Relaxed);
+        self.verify_checked.store(0, Ordering::Relaxed);
+        self.verify_issues.store(0, Ordering::Relaxed);
+        self.verifying.store(true, Ordering::Relaxed);
+    }
+    pub fn verify_checked(&self, has_issue: bool) {
+        self.verify_checked.fetch_add(1, Ordering::Relaxed);
+        if has_issue {
+            self.verify_issues.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    pub fn finish_verify(&self) {
+        self.verifying.store(false, Ordering::Relaxed);
+    }
+
     #[inline]
     fn handled(&self) -> u64 {
         self.handled.load(Ordering::Relaxed)
@@ -313,6 +360,21 @@ impl Component for Dashboard {
             self.pending(),
             avg_str
         );
+        if self.scanning.load(Ordering::Relaxed) {
+            stats.push_str(&format!(
+                " │ Scan: {} checked, {} queued",
+                self.scan_checked.load(Ordering::Relaxed),
+                self.scan_queued.load(Ordering::Relaxed)
+            ));
+        }
+        if self.verifying.load(Ordering::Relaxed) {
+            stats.push_str(&format!(
+                " │ Verify: {}/{}, {} with issues",
+                self.verify_checked.load(Ordering::Relaxed),
+                self.verify_total.load(Ordering::Relaxed),
+                self.verify_issues.load(Ordering::Relaxed)
+            ));
+        }
         // Pad right to fill the terminal width
         stats.push_str(&" ".repeat(cols.saturating_sub(UnicodeWidthStr::width(stats.as_str()))));
         lines.push(Line::sanitized(&stats));
@@ -50,8 +50,8 @@ impl Claims {
             _ => false,
         }
     }
-    pub fn get_share(self) -> Option<ResolvedShare> {
-        match self.role {
+    pub fn share(&self) -> Option<&ResolvedShare> {
+        match &self.role {
             Role::Share(share) => Some(share),
             _ => None,
         }
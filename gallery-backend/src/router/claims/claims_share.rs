@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use arrayvec::ArrayString;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::public::structure::album::Share;
+use crate::router::post::authenticate::JSON_WEB_TOKEN_SECRET_KEY;
+
+/// Proof that the password of one share link was entered.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimsShare {
+    pub album_id: ArrayString<64>,
+    pub share_id: ArrayString<64>,
+    /// [`Share::password_version`] when the token was issued.
+    pub password_version: u32,
+    pub exp: u64,
+}
+
+impl ClaimsShare {
+    pub fn new(album_id: ArrayString<64>, share_id: ArrayString<64>, share: &Share) -> Self {
+        let exp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            + 86_400; // 1 day
+
+        Self {
+            album_id,
+            share_id,
+            password_version: share.password_version,
+            exp,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        self.encode_with(&JSON_WEB_TOKEN_SECRET_KEY)
+    }
+
+    /// Fails if the token is malformed, forged or expired.
+    pub fn decode(token: &str) -> Result<Self> {
+        Self::decode_with(token, &JSON_WEB_TOKEN_SECRET_KEY)
+    }
+
+    /// Whether the token unlocks `share`, stored as `share_id` of album `album_id`, with the
+    /// password it has now.
+    pub fn grants(&self, album_id: &str, share_id: &str, share: &Share) -> bool {
+        self.album_id.as_str() == album_id
+            && self.share_id.as_str() == share_id
+            && self.password_version == share.password_version
+    }
+
+    fn encode_with(&self, secret: &[u8]) -> String {
+        encode(&Header::default(), self, &EncodingKey::from_secret(secret))
+            .expect("Failed to generate token")
+    }
+
+    fn decode_with(token: &str, secret: &[u8]) -> Result<Self> {
+        let token_data = decode::<Self>(
+            token,
+            &DecodingKey::from_secret(secret),
+            &Validation::new(Algorithm::HS256),
+        )
+        .context("Failed to decode share token")?;
+        Ok(token_data.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"test secret";
+
+    fn claims() -> ClaimsShare {
+        ClaimsShare::new(
+            ArrayString::from("album").unwrap(),
+            ArrayString::from("share").unwrap(),
+            &Share::default(),
+        )
+    }
+
+    #[test]
+    fn token_round_trips_and_grants_its_share_only() {
+        let share = Share::default();
+        let token = claims().encode_with(SECRET);
+        let decoded = ClaimsShare::decode_with(&token, SECRET).unwrap();
+        assert!(decoded.grants("album", "share", &share));
+        assert!(!decoded.grants("album", "other", &share));
+        assert!(!decoded.grants("other", "share", &share));
+    }
+
+    #[test]
+    fn password_change_revokes_token() {
+        let mut share = Share::default();
+        share.set_password(Some("secret")).unwrap();
+        let claims = ClaimsShare::new(
+            ArrayString::from("album").unwrap(),
+            ArrayString::from("share").unwrap(),
+            &share,
+        );
+        assert!(claims.grants("album", "share", &share));
+        share.set_password(Some("changed")).unwrap();
+        assert!(!claims.grants("album", "share", &share));
+    }
+
+    #[test]
+    fn token_signed_with_another_secret_is_rejected() {
+        let token = claims().encode_with(b"another secret");
+        assert!(ClaimsShare::decode_with(&token, SECRET).is_err());
+        assert!(ClaimsShare::decode_with("not a token", SECRET).is_err());
+    }
+
+    #[test]
+    fn expired_token_is_rejected() {
+        let mut expired = claims();
+        expired.exp -= 2 * 86_400;
+        let token = expired.encode_with(SECRET);
+        assert!(ClaimsShare::decode_with(&token, SECRET).is_err());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod claims;
 pub mod claims_hash;
+pub mod claims_share;
 pub mod claims_timestamp;
@@ -5,6 +5,7 @@ use crate::router::fairing::guard_auth::GuardAuth;
 use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
 use crate::router::{AppResult, GuardResult};
 use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::collect_garbage::CollectGarbageTask;
 use crate::tasks::batcher::flush_tree::FlushTreeTask;
 use crate::tasks::batcher::update_tree::UpdateTreeTask;
 use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
@@ -32,9 +33,17 @@ pub async fn delete_data(
     })
     .await??;
 
+    let removed_database_list = abstract_data_to_remove
+        .iter()
+        .filter_map(|abstract_data| match abstract_data {
+            AbstractData::Database(database) => Some(database.clone()),
+            AbstractData::Album(_) => None,
+        })
+        .collect();
     BATCH_COORDINATOR
         .execute_batch_waiting(FlushTreeTask::remove(abstract_data_to_remove))
         .await?;
+    BATCH_COORDINATOR.execute_batch_detached(CollectGarbageTask::removed(removed_database_list));
 
     BATCH_COORDINATOR
         .execute_batch_waiting(UpdateTreeTask)
@@ -1,15 +1,19 @@
 use crate::public::constant::redb::ALBUM_TABLE;
 use crate::public::db::tree::TREE;
-use crate::public::structure::album::ResolvedShare;
+use crate::public::structure::album::{Album, ResolvedShare, Share};
+use crate::router::GuardError;
 use crate::router::claims::claims::Claims;
+use crate::router::claims::claims_share::ClaimsShare;
 use crate::router::post::authenticate::JSON_WEB_TOKEN_SECRET_KEY;
 use anyhow::Error;
 use anyhow::Result;
 use anyhow::anyhow;
-use arrayvec::ArrayString;
 use jsonwebtoken::{DecodingKey, Validation, decode};
+use redb::ReadableTable;
 use rocket::Request;
+use rocket::http::Status;
 use serde::de::DeserializeOwned;
+
 /// Extract and validate Authorization header Bearer token
 pub fn extract_bearer_token<'a>(req: &'a Request<'_>) -> Result<&'a str> {
     let auth_header = match req.headers().get_one("Authorization") {
@@ -73,98 +77,140 @@ pub fn extract_hash_from_path(req: &Request<'_>) -> Result<String> {
     }
 }
 
+pub const SHARE_TOKEN_REQUIRED: &str = "Share token required";
+pub const SHARE_TOKEN_INVALID: &str = "Share token is invalid";
+
 /// Try to resolve album and share from headers
-pub fn try_resolve_share_from_headers(req: &Request<'_>) -> Result<Option<Claims>> {
+pub fn try_resolve_share_from_headers(req: &Request<'_>) -> Result<Option<Claims>, GuardError> {
     let album_id = req.headers().get_one("x-album-id");
     let share_id = req.headers().get_one("x-share-id");
+    let share_token = req.headers().get_one("x-share-token");
 
     match (album_id, share_id) {
         (None, None) => Ok(None),
 
-        (Some(_), None) | (None, Some(_)) => Err(anyhow!(
-            "Both x-album-id and x-share-id must be provided together"
-        )),
+        (Some(_), None) | (None, Some(_)) => {
+            Err(anyhow!("Both x-album-id and x-share-id must be provided together").into())
+        }
 
         (Some(album_id), Some(share_id)) => {
-            // 只要帶了，就在這裡定生死：找不到或出錯都回 Err
-            let read_txn = TREE
-                .in_disk
-                .begin_read()
-                .map_err(|_| anyhow!("Failed to begin read transaction"))?;
-
-            let table = read_txn
-                .open_table(ALBUM_TABLE)
-                .map_err(|_| anyhow!("Failed to open album table"))?;
-
-            let album_guard = table
-                .get(album_id)
-                .map_err(|_| anyhow!("Failed to get album from table"))?
-                .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?;
-
-            let mut album = album_guard.value();
-
-            let share = album
-                .share_list
-                .remove(share_id)
-                .ok_or_else(|| anyhow!("Share '{}' not found in album '{}'", share_id, album_id))?;
-
-            let resolved_share = ResolvedShare::new(
-                ArrayString::<64>::from(album_id)
-                    .map_err(|_| anyhow!("Failed to parse album_id"))?,
-                album.title,
-                share,
-            );
-            let claims = Claims::new_share(resolved_share);
-            Ok(Some(claims))
+            resolve_share(album_id, share_id, share_token).map(Some)
         }
     }
 }
 
 /// Try to resolve album and share from query parameters
-pub fn try_resolve_share_from_query(req: &Request<'_>) -> Result<Option<Claims>> {
+pub fn try_resolve_share_from_query(req: &Request<'_>) -> Result<Option<Claims>, GuardError> {
     let album_id = req.query_value::<&str>("albumId").and_then(Result::ok);
     let share_id = req.query_value::<&str>("shareId").and_then(Result::ok);
+    let share_token = req.query_value::<&str>("shareToken").and_then(Result::ok);
 
     match (album_id, share_id) {
         (None, None) => Ok(None),
 
-        (Some(_), None) | (None, Some(_)) => Err(anyhow!(
-            "Both albumId and shareId must be provided together"
-        )),
+        (Some(_), None) | (None, Some(_)) => {
+            Err(anyhow!("Both albumId and shareId must be provided together").into())
+        }
 
         (Some(album_id), Some(share_id)) => {
-            // 只要帶了，就在這裡定生死：找不到或出錯都回 Err
-            let read_txn = TREE
-                .in_disk
-                .begin_read()
-                .map_err(|_| anyhow!("Failed to begin read transaction"))?;
-
-            let table = read_txn
-                .open_table(ALBUM_TABLE)
-                .map_err(|_| anyhow!("Failed to open album table"))?;
-
-            let album_guard = table
-                .get(album_id)
-                .map_err(|_| anyhow!("Failed to get album from table"))?
-                .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?;
-
-            let mut album = album_guard.value();
-
-            let share = album
-                .share_list
-                .remove(share_id)
-                .ok_or_else(|| anyhow!("Share '{}' not found in album '{}'", share_id, album_id))?;
-
-            let resolved_share = ResolvedShare::new(
-                ArrayString::<64>::from(album_id)
-                    .map_err(|_| anyhow!("Failed to parse album_id"))?,
-                album.title,
-                share,
-            );
-            let claims = Claims::new_share(resolved_share);
-            Ok(Some(claims))
+            resolve_share(album_id, share_id, share_token).map(Some)
+        }
+    }
+}
+
+/// Look up a share and the album it currently belongs to. Shares move when albums are merged,
+/// so links that still name the old album are resolved by share id alone.
+pub fn find_share(
+    table: &impl ReadableTable<&'static str, Album>,
+    album_id: &str,
+    share_id: &str,
+) -> Result<Option<(Album, Share)>> {
+    if let Some(album_guard) = table.get(album_id)? {
+        let mut album = album_guard.value();
+        if let Some(share) = album.share_list.remove(share_id) {
+            return Ok(Some((album, share)));
+        }
+    }
+    for entry in table.iter()? {
+        let (_, album_guard) = entry?;
+        let mut album = album_guard.value();
+        if let Some(share) = album.share_list.remove(share_id) {
+            return Ok(Some((album, share)));
         }
     }
+    Ok(None)
+}
+
+// 只要帶了，就在這裡定生死：找不到或出錯都回 Err
+fn resolve_share(
+    album_id: &str,
+    share_id: &str,
+    share_token: Option<&str>,
+) -> Result<Claims, GuardError> {
+    let read_txn = TREE
+        .in_disk
+        .begin_read()
+        .map_err(|_| server_error(anyhow!("Failed to begin read transaction")))?;
+
+    let table = read_txn
+        .open_table(ALBUM_TABLE)
+        .map_err(|_| server_error(anyhow!("Failed to open album table")))?;
+
+    let (album, share) = find_share(&table, album_id, share_id)
+        .map_err(server_error)?
+        .ok_or_else(|| anyhow!("Share '{}' not found in album '{}'", share_id, album_id))?;
+    if share.is_expired() {
+        return Err(GuardError::new(
+            Status::Gone,
+            anyhow!("Share '{}' has expired", share_id),
+        ));
+    }
+    check_share_token(&share, &album.id, share_id, share_token)?;
+
+    let resolved_share = ResolvedShare::new(album.id, album.title, share);
+    Ok(Claims::new_share(resolved_share))
+}
+
+fn server_error(err: Error) -> GuardError {
+    GuardError::new(Status::InternalServerError, err)
+}
+
+/// Shares with a password or a view limit also need the token issued by
+/// `/post/authenticate_share`; see [`Share::needs_token`].
+///
+/// Rejections are `401` with a top-level message of [`SHARE_TOKEN_REQUIRED`] or
+/// [`SHARE_TOKEN_INVALID`], so clients know to ask for a token. Without a token, a share whose
+/// views are used up is `410` instead.
+fn check_share_token(
+    share: &Share,
+    album_id: &str,
+    share_id: &str,
+    share_token_opt: Option<&str>,
+) -> Result<(), GuardError> {
+    if !share.needs_token() {
+        return Ok(());
+    }
+    let Some(share_token) = share_token_opt else {
+        if share.is_exhausted() {
+            return Err(GuardError::new(
+                Status::Gone,
+                anyhow!("Share '{}' has reached its view limit", share_id),
+            ));
+        }
+        return Err(anyhow!("Share '{}' needs a share token", share_id)
+            .context(SHARE_TOKEN_REQUIRED)
+            .into());
+    };
+    let claims =
+        ClaimsShare::decode(share_token).map_err(|err| err.context(SHARE_TOKEN_INVALID))?;
+    if !claims.grants(album_id, share_id, share) {
+        return Err(
+            anyhow!("Share token was issued for another share or password")
+                .context(SHARE_TOKEN_INVALID)
+                .into(),
+        );
+    }
+    Ok(())
 }
 
 /// Try to authorize upload via share headers with upload permission
@@ -175,15 +221,16 @@ pub fn try_authorize_upload_via_share(req: &Request<'_>) -> bool {
     if let (Some(album_id), Some(share_id)) = (album_id, share_id) {
         if let Ok(read_txn) = TREE.in_disk.begin_read() {
             if let Ok(table) = read_txn.open_table(ALBUM_TABLE) {
-                if let Ok(Some(album_guard)) = table.get(album_id) {
-                    let mut album = album_guard.value();
-                    if let Some(share) = album.share_list.remove(share_id) {
-                        if share.show_upload {
-                            if let Some(Ok(album_id_parsed)) =
-                                req.query_value::<&str>("presigned_album_id_opt")
-                            {
-                                return album.id.as_str() == album_id_parsed;
-                            }
+                if let Ok(Some((album, share))) = find_share(&table, album_id, share_id) {
+                    let share_token = req.headers().get_one("x-share-token");
+                    if share.show_upload
+                        && !share.is_expired()
+                        && check_share_token(&share, &album.id, share_id, share_token).is_ok()
+                    {
+                        if let Some(Ok(album_id_parsed)) =
+                            req.query_value::<&str>("presigned_album_id_opt")
+                        {
+                            return album.id.as_str() == album_id_parsed;
                         }
                     }
                 }
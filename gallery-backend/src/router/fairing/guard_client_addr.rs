@@ -0,0 +1,29 @@
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use std::net::IpAddr;
+
+use crate::public::config::SERVER_CONFIG;
+use crate::router::GuardError;
+
+/// Address of the client: the peer address, or the proxy's client IP header when
+/// `share.trustedProxy` is set.
+pub struct GuardClientAddr(pub Option<IpAddr>);
+
+impl GuardClientAddr {
+    pub fn of(req: &Request<'_>) -> Self {
+        if SERVER_CONFIG.share.trusted_proxy {
+            Self(req.client_ip())
+        } else {
+            Self(req.remote().map(|remote| remote.ip()))
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for GuardClientAddr {
+    type Error = GuardError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(GuardClientAddr::of(req))
+    }
+}
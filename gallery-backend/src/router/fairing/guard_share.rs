@@ -6,11 +6,67 @@ use super::VALIDATION;
 use super::auth_utils::{
     try_jwt_cookie_auth, try_resolve_share_from_headers, try_resolve_share_from_query,
 };
+use super::guard_client_addr::GuardClientAddr;
+use crate::public::structure::share_access::{ShareAccess, ShareAccessKind};
 use crate::router::GuardError;
 use crate::router::claims::claims::Claims;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::record_share_access::RecordShareAccessTask;
+use arrayvec::ArrayString;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longer User-Agent headers are cut short before they are logged.
+const MAX_USER_AGENT_LEN: usize = 256;
 
 pub struct GuardShare {
     pub claims: Claims,
+    /// Who made the request, for the share access log.
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl GuardShare {
+    fn new(req: &Request<'_>, claims: Claims) -> Self {
+        Self {
+            claims,
+            ip: GuardClientAddr::of(req).0.map(|ip| ip.to_string()),
+            user_agent: req
+                .headers()
+                .get_one("User-Agent")
+                .map(|user_agent| truncate(user_agent, MAX_USER_AGENT_LEN).to_string()),
+        }
+    }
+
+    /// Log the request in the share access log if it came through a share link.
+    pub fn record_access(&self, kind: ShareAccessKind, hash: Option<ArrayString<64>>) {
+        let Some(resolved_share) = self.claims.share() else {
+            return;
+        };
+        BATCH_COORDINATOR.execute_batch_detached(RecordShareAccessTask::new(ShareAccess {
+            album_id: resolved_share.album_id,
+            share_id: resolved_share.share.url,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+            ip: self.ip.clone(),
+            user_agent: self.user_agent.clone(),
+            kind,
+            hash,
+        }));
+    }
+}
+
+/// At most `max_len` bytes of `text`, cut at a character boundary.
+fn truncate(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
 }
 
 #[rocket::async_trait]
@@ -20,37 +76,40 @@ impl<'r> FromRequest<'r> for GuardShare {
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         // headers
         match try_resolve_share_from_headers(req) {
-            Ok(Some(claims)) => return Outcome::Success(GuardShare { claims }),
+            Ok(Some(claims)) => return Outcome::Success(GuardShare::new(req, claims)),
             Ok(None) => {}
-            Err(err) => {
-                return Outcome::Error((
-                    Status::InternalServerError,
-                    err.context("Header share resolution failed").into(),
-                ));
-            }
+            Err(err) => return Outcome::Error((err.status, err)),
         }
 
         // query
         match try_resolve_share_from_query(req) {
-            Ok(Some(claims)) => return Outcome::Success(GuardShare { claims }),
+            Ok(Some(claims)) => return Outcome::Success(GuardShare::new(req, claims)),
             Ok(None) => {}
-            Err(err) => {
-                return Outcome::Error((
-                    Status::InternalServerError,
-                    err.context("Query share resolution failed").into(),
-                ));
-            }
+            Err(err) => return Outcome::Error((err.status, err)),
         }
 
         // Fall back to JWT cookie authentication
         match try_jwt_cookie_auth(req, &VALIDATION) {
-            Ok(claims) => return Outcome::Success(GuardShare { claims }),
+            Ok(claims) => return Outcome::Success(GuardShare::new(req, claims)),
             Err(err) => {
                 return Outcome::Error((
-                    Status::InternalServerError,
+                    Status::Unauthorized,
                     err.context("Authentication error").into(),
                 ));
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_whole_characters() {
+        assert_eq!(truncate("short", 256), "short");
+        assert_eq!(truncate("abcdef", 3), "abc");
+        // "é" takes two bytes
+        assert_eq!(truncate("aé", 2), "a");
+    }
+}
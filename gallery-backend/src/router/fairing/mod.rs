@@ -6,6 +6,7 @@ use rocket::Route;
 pub mod auth_utils;
 pub mod cache_control_fairing;
 pub mod guard_auth;
+pub mod guard_client_addr;
 pub mod guard_hash;
 pub mod guard_read_only_mode;
 pub mod guard_share;
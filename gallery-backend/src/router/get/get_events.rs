@@ -0,0 +1,38 @@
+use crate::public::constant::redb::EVENT_PROPOSAL_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::structure::event_proposal::{EventProposal, ProposalStatus};
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::{AppResult, GuardResult};
+use anyhow::Result;
+use redb::ReadableTable;
+use rocket::serde::json::Json;
+
+/// Open event proposals, oldest first.
+#[get("/get/get-event-proposals")]
+pub async fn get_event_proposals(
+    auth: GuardResult<GuardAuth>,
+) -> AppResult<Json<Vec<EventProposal>>> {
+    let _ = auth?;
+    let proposal_list = tokio::task::spawn_blocking(|| -> Result<Vec<EventProposal>> {
+        let read_txn = TREE.in_disk.begin_read()?;
+        let table = match read_txn.open_table(EVENT_PROPOSAL_TABLE) {
+            Ok(table) => table,
+            // Clustering has never run.
+            Err(redb::TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut proposal_list = table
+            .iter()?
+            .map(|entry| entry.map(|(_, value)| value.value()))
+            .filter(|proposal| {
+                proposal
+                    .as_ref()
+                    .map_or(true, |proposal| proposal.status == ProposalStatus::Proposed)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        proposal_list.sort_by_key(|proposal| proposal.start_time);
+        Ok(proposal_list)
+    })
+    .await??;
+    Ok(Json(proposal_list))
+}
@@ -1,3 +1,7 @@
+use crate::operations::open_db::open_data_table;
+use crate::public::storage::STORAGE;
+use crate::public::structure::database_struct::database::definition::StorageMode;
+use crate::public::structure::share_access::ShareAccessKind;
 use crate::router::{
     AppResult, GuardResult,
     fairing::{
@@ -6,25 +10,146 @@ use crate::router::{
     },
 };
 use anyhow::Context;
+use arrayvec::ArrayString;
 use rocket::fs::NamedFile;
-use rocket::response::Responder;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
 use rocket_seek_stream::SeekStream;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use tokio::io::{AsyncWriteExt, DuplexStream};
+
+/// Upper bound for one open-ended range read from a remote backend.
+const MAX_OBJECT_CHUNK: u64 = 8 * 1024 * 1024;
+
+/// Buffer between the blocking storage reader and the response body.
+const OBJECT_STREAM_BUFFER: usize = 64 * 1024;
+
 #[derive(Responder)]
 pub enum CompressedFileResponse<'a> {
     SeekStream(SeekStream<'a>),
     NamedFile(NamedFile),
+    Object(ObjectResponse),
+}
+
+/// The raw `Range` header, needed to serve byte ranges from remote storage.
+pub struct RangeHeader(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RangeHeader {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RangeHeader(
+            req.headers().get_one("Range").map(String::from),
+        ))
+    }
+}
+
+/// An object streamed from `STORAGE`, optionally a single byte range of it.
+pub struct ObjectResponse {
+    body: DuplexStream,
+    content_type: ContentType,
+    /// `(first, last, total)` for a partial response.
+    content_range: Option<(u64, u64, u64)>,
+}
+
+impl<'r> Responder<'r, 'static> for ObjectResponse {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let mut builder = Response::build();
+        builder
+            .header(self.content_type)
+            .raw_header("Accept-Ranges", "bytes");
+        if let Some((first, last, total)) = self.content_range {
+            builder.status(Status::PartialContent).raw_header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", first, last, total),
+            );
+        }
+        builder.streamed_body(self.body).ok()
+    }
+}
+
+async fn fetch_object(key: String, range_header: Option<String>) -> anyhow::Result<ObjectResponse> {
+    let content_type = Path::new(&key)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ContentType::from_extension)
+        .unwrap_or(ContentType::Binary);
+    let (source, content_range) = tokio::task::spawn_blocking(move || {
+        let range_opt = match range_header {
+            Some(header) => parse_range(&header, STORAGE.size(&key)?),
+            None => None,
+        };
+        let source = STORAGE.open(&key, range_opt.map(|(first, last, _)| first..last + 1))?;
+        anyhow::Ok((source, range_opt))
+    })
+    .await??;
+
+    let (body, mut writer) = tokio::io::duplex(OBJECT_STREAM_BUFFER);
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let mut source = source;
+        let mut buffer = vec![0; OBJECT_STREAM_BUFFER];
+        loop {
+            let read = match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) => {
+                    error!("Failed to stream object: {}", err);
+                    break;
+                }
+            };
+            // An error here means the client went away.
+            if handle.block_on(writer.write_all(&buffer[..read])).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(ObjectResponse {
+        body,
+        content_type,
+        content_range,
+    })
+}
+
+/// Parse a single `bytes=` range into `(first, last, total)`.
+fn parse_range(header: &str, total: u64) -> Option<(u64, u64, u64)> {
+    let (start, end) = header.strip_prefix("bytes=")?.split_once('-')?;
+    if total == 0 {
+        return None;
+    }
+    let (first, last) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (total.saturating_sub(suffix), total - 1)
+        }
+        (start, "") => {
+            let first: u64 = start.parse().ok()?;
+            (first, (first + MAX_OBJECT_CHUNK - 1).min(total - 1))
+        }
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(total - 1)),
+    };
+    (first <= last).then_some((first, last, total))
 }
 
 #[get("/object/compressed/<file_path..>")]
 pub async fn compressed_file(
     auth_guard: GuardResult<GuardShare>,
     hash_guard: GuardResult<GuardHash>,
+    range: RangeHeader,
     file_path: PathBuf,
 ) -> AppResult<CompressedFileResponse<'static>> {
-    let _ = auth_guard?;
+    let auth_guard = auth_guard?;
     let _ = hash_guard?;
-    let compressed_file_path = Path::new("./object/compressed").join(&file_path);
+    auth_guard.record_access(ShareAccessKind::View, path_hash(&file_path));
+    let key = Path::new("compressed").join(&file_path);
+    let key = key.to_string_lossy().into_owned();
+    let Some(compressed_file_path) = STORAGE.local_path(&key) else {
+        let object = fetch_object(key, range.0).await?;
+        return Ok(CompressedFileResponse::Object(object));
+    };
 
     let result = match compressed_file_path
         .extension()
@@ -64,11 +189,27 @@ pub async fn compressed_file(
 pub async fn imported_file(
     auth: GuardResult<GuardShare>,
     hash_guard: GuardResult<GuardHashOriginal>,
+    range: RangeHeader,
     file_path: PathBuf,
 ) -> AppResult<CompressedFileResponse<'static>> {
-    let _ = auth?;
+    let auth = auth?;
     let _ = hash_guard?;
-    let imported_file_path = Path::new("./object/imported").join(&file_path);
+    auth.record_access(ShareAccessKind::Download, path_hash(&file_path));
+    let key = Path::new("imported").join(&file_path);
+    let key = key.to_string_lossy().into_owned();
+    let local_path_opt = STORAGE.local_path(&key);
+    let imported_file_path = match local_path_opt {
+        Some(path) if path.is_file() => path,
+        // Reference-only items are served straight from one of their sources.
+        _ => match resolve_reference_path(&file_path).await? {
+            Some(source_path) => source_path,
+            None if local_path_opt.is_none() => {
+                let object = fetch_object(key, range.0).await?;
+                return Ok(CompressedFileResponse::Object(object));
+            }
+            None => Path::new("./object").join(&key),
+        },
+    };
     NamedFile::open(imported_file_path)
         .await
         .map(CompressedFileResponse::NamedFile)
@@ -77,3 +218,27 @@ pub async fn imported_file(
             anyhow::anyhow!("Error opening imported file: {:#?}", error).into()
         })
 }
+
+/// The hash in a `<xx>/<hash>.<ext>` object path.
+fn path_hash(file_path: &Path) -> Option<ArrayString<64>> {
+    file_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| ArrayString::<64>::from(stem).ok())
+}
+
+/// Look up the item named by `<xx>/<hash>.<ext>` and, if it is reference-only, find a source.
+async fn resolve_reference_path(file_path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let hash = path_hash(file_path)
+        .ok_or_else(|| anyhow::anyhow!("Invalid imported file path: {}", file_path.display()))?;
+    tokio::task::spawn_blocking(move || {
+        let data_table = open_data_table()?;
+        let original_path_opt = data_table
+            .get(&*hash)?
+            .map(|guard| guard.value())
+            .filter(|database| database.storage == StorageMode::Reference)
+            .and_then(|database| database.original_path_opt());
+        Ok(original_path_opt)
+    })
+    .await?
+}
@@ -0,0 +1,13 @@
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::batcher::verify_integrity::{INTEGRITY_REPORT, IntegrityReport};
+use rocket::serde::json::Json;
+
+/// The last integrity report, or `null` if verification has never run.
+#[get("/get/get-integrity-report")]
+pub async fn get_integrity_report(
+    auth: GuardResult<GuardAuth>,
+) -> AppResult<Json<Option<IntegrityReport>>> {
+    let _ = auth?;
+    Ok(Json(INTEGRITY_REPORT.read().unwrap().clone()))
+}
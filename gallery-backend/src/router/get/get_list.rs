@@ -1,7 +1,8 @@
 use crate::public::config::{PUBLIC_CONFIG, PublicConfig};
 use crate::public::db::tree::TREE;
 use crate::public::db::tree::read_tags::TagInfo;
-use crate::public::structure::album::Share;
+use crate::public::structure::album::{AlbumSortMode, Share};
+use crate::public::structure::share_access::{ShareAccess, ShareStats};
 use crate::router::fairing::guard_auth::GuardAuth;
 use crate::router::fairing::guard_share::GuardShare;
 use crate::router::{AppResult, GuardResult};
@@ -32,7 +33,12 @@ pub async fn get_tags(auth: GuardResult<GuardAuth>) -> AppResult<Json<Vec<TagInf
 pub struct AlbumInfo {
     pub album_id: String,
     pub album_name: Option<String>,
+    pub parent_id: Option<ArrayString<64>>,
+    pub include_children: bool,
+    pub sort_mode: AlbumSortMode,
     pub share_list: HashMap<ArrayString<64>, Share>,
+    /// Access counts per share id.
+    pub share_stats: HashMap<ArrayString<64>, ShareStats>,
 }
 
 #[get("/get/get-albums")]
@@ -40,11 +46,26 @@ pub async fn get_albums(auth: GuardResult<GuardAuth>) -> AppResult<Json<Vec<Albu
     let _ = auth?;
     tokio::task::spawn_blocking(move || {
         let album_list = TREE.read_albums().context("Failed to read albums")?;
+        let share_id_list: Vec<_> = album_list
+            .iter()
+            .flat_map(|album| album.share_list.keys().copied())
+            .collect();
+        let mut share_stats = TREE
+            .read_share_stats(&share_id_list)
+            .context("Failed to read share statistics")?;
         let album_info_list = album_list
             .into_iter()
             .map(|album| AlbumInfo {
                 album_id: album.id.to_string(),
                 album_name: album.title,
+                parent_id: album.parent_id,
+                include_children: album.include_children,
+                sort_mode: album.sort_mode,
+                share_stats: album
+                    .share_list
+                    .keys()
+                    .filter_map(|share_id| share_stats.remove_entry(share_id))
+                    .collect(),
                 share_list: album.share_list,
             })
             .collect();
@@ -52,3 +73,20 @@ pub async fn get_albums(auth: GuardResult<GuardAuth>) -> AppResult<Json<Vec<Albu
     })
     .await?
 }
+
+/// The latest accesses through one share link, newest first.
+#[get("/get/get-share-access-log?<share_id>&<limit>")]
+pub async fn get_share_access_log(
+    auth: GuardResult<GuardAuth>,
+    share_id: String,
+    limit: Option<usize>,
+) -> AppResult<Json<Vec<ShareAccess>>> {
+    let _ = auth?;
+    tokio::task::spawn_blocking(move || {
+        let access_list = TREE
+            .read_share_access(&share_id, limit.unwrap_or(100))
+            .context("Failed to read share access log")?;
+        Ok(Json(access_list))
+    })
+    .await?
+}
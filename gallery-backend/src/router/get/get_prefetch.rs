@@ -1,11 +1,13 @@
+use crate::operations::open_db::open_album_table;
 use crate::public::db::query_snapshot::QUERY_SNAPSHOT;
 use crate::public::db::tree::TREE;
 use crate::public::db::tree::VERSION_COUNT_TIMESTAMP;
 use crate::public::db::tree_snapshot::TREE_SNAPSHOT;
-use crate::public::structure::album::ResolvedShare;
+use crate::public::structure::album::{AlbumSortMode, ResolvedShare};
 use crate::public::structure::database_struct::database_timestamp::DatabaseTimestamp;
 use crate::public::structure::expression::Expression;
 use crate::public::structure::reduced_data::ReducedData;
+use crate::public::structure::share_access::ShareAccessKind;
 use crate::router::AppResult;
 use crate::router::GuardResult;
 use crate::router::claims::claims_timestamp::ClaimsTimestamp;
@@ -16,11 +18,13 @@ use crate::tasks::batcher::flush_query_snapshot::FlushQuerySnapshotTask;
 use crate::tasks::batcher::flush_tree_snapshot::FlushTreeSnapshotTask;
 
 use anyhow::{Result, anyhow};
+use arrayvec::ArrayString;
 use bitcode::{Decode, Encode};
 use log::info;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::hash::Hasher;
 use std::hash::{DefaultHasher, Hash};
 use std::mem;
@@ -108,37 +112,38 @@ fn check_query_cache(
 fn filter_items(
     expression_option: Option<Expression>,
     resolved_share_option: &Option<ResolvedShare>,
+    order_album_option: Option<ArrayString<64>>,
 ) -> Result<Vec<ReducedData>> {
     let filter_items_start_time = Instant::now();
 
-    let tree_guard = TREE.in_memory.read().map_err(|err| anyhow!("{:?}", err))?;
-    let reduced_data_vector: Vec<ReducedData> = match (expression_option, &resolved_share_option) {
+    // Built before taking the lock: album subtrees are resolved from the tree while building
+    let filter_opt = match (expression_option, &resolved_share_option) {
         // If we have a resolved share then it must have a filter expression
-        (Some(expr), Some(resolved_share)) => {
-            let filter_fn = if resolved_share.share.show_metadata {
-                expr.generate_filter()
-            } else {
-                expr.generate_filter_hide_metadata(resolved_share.album_id)
-            };
-            tree_guard
-                .par_iter()
-                .filter(|db_ts| filter_fn(&db_ts.abstract_data))
-                .map(|db_ts| db_ts.into())
-                .collect()
-        }
-        (Some(expr), None) => {
-            let filter_fn = expr.generate_filter();
-            tree_guard
-                .par_iter()
-                .filter(|database_timestamp| filter_fn(&database_timestamp.abstract_data))
-                .map(|database_timestamp| database_timestamp.into())
-                .collect()
+        (Some(expr), Some(resolved_share)) if !resolved_share.share.show_metadata => {
+            Some(expr.generate_filter_hide_metadata(resolved_share.album_id))
         }
-        (None, _) => tree_guard
+        (Some(expr), _) => Some(expr.generate_filter()),
+        (None, _) => None,
+    };
+
+    let tree_guard = TREE.in_memory.read().map_err(|err| anyhow!("{:?}", err))?;
+    let reduced_data_vector: Vec<ReducedData> = match filter_opt {
+        Some(filter_fn) => tree_guard
+            .par_iter()
+            .filter(|database_timestamp| filter_fn(&database_timestamp.abstract_data))
+            .map(|database_timestamp| database_timestamp.into())
+            .collect(),
+        None => tree_guard
             .par_iter()
             .map(|database_timestamp| database_timestamp.into())
             .collect(),
     };
+    drop(tree_guard);
+
+    let mut reduced_data_vector = reduced_data_vector;
+    if let Some(album_id) = order_album_option {
+        apply_album_order(&mut reduced_data_vector, album_id)?;
+    }
 
     let duration = format!("{:?}", filter_items_start_time.elapsed());
     info!(duration = &*duration; "Filter items");
@@ -146,6 +151,39 @@ fn filter_items(
     Ok(reduced_data_vector)
 }
 
+/// Reorder the items of a single album view by the album's `sort_mode`; they arrive newest first.
+fn apply_album_order(
+    reduced_data_vector: &mut [ReducedData],
+    album_id: ArrayString<64>,
+) -> Result<()> {
+    let Some(album) = open_album_table()
+        .get(&*album_id)?
+        .map(|guard| guard.value())
+    else {
+        return Ok(());
+    };
+    match album.sort_mode {
+        AlbumSortMode::DateDesc => {}
+        AlbumSortMode::DateAsc => reduced_data_vector.reverse(),
+        AlbumSortMode::Custom => {
+            let position_map: HashMap<_, _> = album
+                .custom_order
+                .iter()
+                .enumerate()
+                .map(|(position, hash)| (*hash, position))
+                .collect();
+            // Stable, so items without a position stay newest first after the placed ones
+            reduced_data_vector.sort_by_key(|reduced| {
+                position_map
+                    .get(&reduced.hash)
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            });
+        }
+    }
+    Ok(())
+}
+
 fn compute_locate(
     reduced_data_vector: &[ReducedData],
     locate_option: &Option<String>,
@@ -240,6 +278,7 @@ fn execute_prefetch_logic(
     expression_option: Option<Expression>,
     locate_option: Option<String>,
     mut resolved_share_option: Option<ResolvedShare>,
+    order_album_option: Option<ArrayString<64>>,
 ) -> Result<Json<PrefetchReturn>> {
     // Start timer
     let start_time = Instant::now();
@@ -253,7 +292,11 @@ fn execute_prefetch_logic(
     }
 
     // Step 3: Filter items
-    let reduced_data_vector = filter_items(expression_option, &resolved_share_option)?;
+    let reduced_data_vector = filter_items(
+        expression_option,
+        &resolved_share_option,
+        order_album_option,
+    )?;
 
     // Step 4: Compute layout
     let locate_to_index = compute_locate(&reduced_data_vector, &locate_option);
@@ -287,10 +330,35 @@ pub async fn prefetch(
     let auth_guard = auth_guard?;
     // Combine album filter (if any) with the client‑supplied query.
     let mut combined_expression_option = query_data.map(|wrapper| wrapper.into_inner());
-    let resolved_share_option = auth_guard.claims.get_share();
+    let resolved_share_option = auth_guard.claims.share().cloned();
+
+    // A single album, opened directly or through its share, follows the album's own sort order
+    let order_album_option = match (&combined_expression_option, &resolved_share_option) {
+        (Some(Expression::Album(album_id)), None) => Some(*album_id),
+        (None, Some(resolved_share)) => Some(resolved_share.album_id),
+        _ => None,
+    };
 
     if let Some(resolved_share) = &resolved_share_option {
-        let album_filter_expression = Expression::Album(resolved_share.album_id);
+        // Opening the share is logged; searches inside it are not. Views are counted once per
+        // visit, when the share token is issued
+        if combined_expression_option.is_none() {
+            auth_guard.record_access(ShareAccessKind::Open, None);
+        }
+
+        // Archived, hidden and trashed items are never shown to share viewers; albums nested
+        // below the shared one only when the share includes them
+        let album_expression = if resolved_share.share.include_children {
+            Expression::AlbumTree(resolved_share.album_id)
+        } else {
+            Expression::Album(resolved_share.album_id)
+        };
+        let album_filter_expression = Expression::And(vec![
+            album_expression,
+            Expression::Not(Box::new(Expression::Archived)),
+            Expression::Not(Box::new(Expression::Hidden)),
+            Expression::Not(Box::new(Expression::Trashed)),
+        ]);
 
         combined_expression_option = Some(match combined_expression_option {
             Some(client_expression) => {
@@ -302,7 +370,12 @@ pub async fn prefetch(
 
     // Execute on blocking thread
     let job_handle = tokio::task::spawn_blocking(move || {
-        execute_prefetch_logic(combined_expression_option, locate, resolved_share_option)
+        execute_prefetch_logic(
+            combined_expression_option,
+            locate,
+            resolved_share_option,
+            order_album_option,
+        )
     })
     .await??;
 
@@ -1,8 +1,10 @@
 use rocket::Route;
 
 pub mod get_data;
+pub mod get_events;
 pub mod get_export;
 pub mod get_img;
+pub mod get_integrity;
 pub mod get_list;
 pub mod get_page;
 pub mod get_prefetch;
@@ -12,11 +14,14 @@ pub fn generate_get_routes() -> Vec<Route> {
         get_list::get_config,
         get_list::get_tags,
         get_list::get_albums,
+        get_list::get_share_access_log,
         get_data::get_data,
         get_data::get_rows,
         get_data::get_scroll_bar,
         get_img::compressed_file,
         get_img::imported_file,
+        get_events::get_event_proposals,
+        get_integrity::get_integrity_report,
         get_page::redirect_to_photo,
         get_page::login,
         get_page::redirect_to_login,
@@ -51,14 +51,25 @@ where
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Why a request guard rejected a request; `401 Unauthorized` unless built with
+/// [`GuardError::new`].
 #[derive(Debug)]
-pub struct GuardError(anyhow::Error);
+pub struct GuardError {
+    pub status: Status,
+    pub error: anyhow::Error,
+}
+
+impl GuardError {
+    pub fn new(status: Status, error: anyhow::Error) -> Self {
+        Self { status, error }
+    }
+}
 
 impl From<GuardError> for AppError {
     fn from(err: GuardError) -> Self {
         AppError {
-            status: Status::Unauthorized,
-            error: err.0,
+            status: err.status,
+            error: err.error,
         }
     }
 }
@@ -70,6 +81,9 @@ where
     anyhow::Error: From<E>,
 {
     fn from(err: E) -> Self {
-        GuardError(anyhow::Error::from(err))
+        GuardError {
+            status: Status::Unauthorized,
+            error: anyhow::Error::from(err),
+        }
     }
 }
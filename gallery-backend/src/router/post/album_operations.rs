@@ -0,0 +1,372 @@
+use anyhow::{Result, anyhow, bail};
+use arrayvec::ArrayString;
+use chrono::{Local, TimeZone};
+use futures::{StreamExt, TryStreamExt, stream};
+use redb::ReadableTable;
+use rocket::post;
+use rocket::serde::json::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use crate::operations::hash::generate_random_hash;
+use crate::operations::open_db::{open_album_table, open_data_table};
+use crate::public::db::tree::TREE;
+use crate::public::db::tree::album_tree::creates_cycle;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::album::{Album, CollageCover};
+use crate::public::structure::database_struct::database::definition::Database;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateAlbum {
+    pub album_id: ArrayString<64>,
+    /// Defaults to the original title with ` (copy)` appended.
+    pub title: Option<String>,
+}
+
+/// Copy an album's metadata and members into a new album; shares are not copied.
+#[post("/post/duplicate_album", data = "<duplicate_album>")]
+pub async fn duplicate_album(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    duplicate_album: Json<DuplicateAlbum>,
+) -> AppResult<String> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let duplicate_album = duplicate_album.into_inner();
+    let new_album_id = generate_random_hash();
+
+    let to_flush = tokio::task::spawn_blocking(move || -> Result<Vec<AbstractData>> {
+        let source = read_album(duplicate_album.album_id)?;
+        let mut album = Album::new(
+            new_album_id,
+            duplicate_album.title.or_else(|| {
+                source
+                    .title
+                    .as_ref()
+                    .map(|title| format!("{} (copy)", title))
+            }),
+        );
+        album.parent_id = source.parent_id;
+        album.include_children = source.include_children;
+        album.user_defined_metadata = source.user_defined_metadata;
+        album.tag = source.tag;
+        album.favorite = source.favorite;
+        album.archived = source.archived;
+        album.hidden = source.hidden;
+        album.sort_mode = source.sort_mode;
+        album.custom_order = source.custom_order;
+        album.cover = source.cover;
+        // Drawn again under the new album's id
+        album.collage = source
+            .collage
+            .map(|collage| CollageCover::new(collage.layout, collage.pinned));
+        album.thumbhash = source.thumbhash;
+
+        let mut to_flush = vec![AbstractData::Album(album)];
+        for (mut database, _) in read_members(source.id)? {
+            database.album.insert(new_album_id);
+            to_flush.push(AbstractData::Database(database));
+        }
+        Ok(to_flush)
+    })
+    .await??;
+
+    flush_and_refresh(to_flush, Vec::new(), vec![new_album_id]).await?;
+    Ok(new_album_id.to_string())
+}
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeAlbums {
+    pub target_album_id: ArrayString<64>,
+    pub source_album_id_array: Vec<ArrayString<64>>,
+}
+
+/// Fold albums into `target_album_id`: members, shares, tags and nested albums move over and the
+/// source albums are deleted. Links to moved shares keep working; see
+/// [`find_share`](crate::router::fairing::auth_utils::find_share).
+#[post("/post/merge_albums", data = "<merge_albums>")]
+pub async fn merge_albums(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    merge_albums: Json<MergeAlbums>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let merge_albums = merge_albums.into_inner();
+    let target_album_id = merge_albums.target_album_id;
+
+    let (to_flush, to_remove) =
+        tokio::task::spawn_blocking(move || -> Result<(Vec<AbstractData>, Vec<AbstractData>)> {
+            let source_id_set: HashSet<_> = merge_albums
+                .source_album_id_array
+                .into_iter()
+                .filter(|album_id| *album_id != target_album_id)
+                .collect();
+            if source_id_set.is_empty() {
+                bail!("No albums to merge into '{}'", target_album_id);
+            }
+
+            let mut target = read_album(target_album_id)?;
+            let mut to_remove = Vec::new();
+            for source_id in &source_id_set {
+                let source = read_album(*source_id)?;
+                target.share_list.extend(source.share_list.clone());
+                target.tag.extend(source.tag.iter().cloned());
+                target
+                    .custom_order
+                    .extend(source.custom_order.iter().copied());
+                to_remove.push(AbstractData::Album(source));
+            }
+
+            let mut to_flush = Vec::new();
+            let mut seen = HashSet::new();
+            for source_id in &source_id_set {
+                for (mut database, _) in read_members(*source_id)? {
+                    if !seen.insert(database.hash) {
+                        continue;
+                    }
+                    database
+                        .album
+                        .retain(|album_id| !source_id_set.contains(album_id));
+                    database.album.insert(target_album_id);
+                    to_flush.push(AbstractData::Database(database));
+                }
+            }
+
+            // Albums filed under a merged album are filed under the target instead
+            let mut parent_of = HashMap::new();
+            for (_, guard) in open_album_table().iter()?.filter_map(|entry| entry.ok()) {
+                let mut album = guard.value();
+                if source_id_set.contains(&album.id) {
+                    continue;
+                }
+                if album.id != target_album_id
+                    && album
+                        .parent_id
+                        .is_some_and(|parent_id| source_id_set.contains(&parent_id))
+                {
+                    album.parent_id = Some(target_album_id);
+                    to_flush.push(AbstractData::Album(album.clone()));
+                }
+                parent_of.insert(album.id, album.parent_id);
+            }
+            if target
+                .parent_id
+                .is_some_and(|parent_id| source_id_set.contains(&parent_id))
+            {
+                target.parent_id = None;
+            }
+            // The target may sit below a merged album, in which case its new children would
+            // include one of its own ancestors
+            if creates_cycle(target_album_id, target.parent_id, |ancestor_id| {
+                Ok(parent_of.get(&ancestor_id).copied().flatten())
+            })? {
+                bail!(
+                    "Album '{}' is nested inside an album being merged into it; move it out first",
+                    target_album_id
+                );
+            }
+            to_flush.push(AbstractData::Album(target));
+            Ok((to_flush, to_remove))
+        })
+        .await??;
+
+    flush_and_refresh(to_flush, to_remove, vec![target_album_id]).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "by", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SplitBy {
+    /// One album per calendar day, in server local time.
+    Day,
+    /// A new album wherever consecutive items are further apart than this.
+    Gap { gap_hours: u64 },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitAlbum {
+    pub album_id: ArrayString<64>,
+    #[serde(flatten)]
+    pub split_by: SplitBy,
+}
+
+/// Move the items of an album into new albums by date. The new albums are filed under the
+/// original, which keeps showing all of them.
+#[post("/post/split_album", data = "<split_album>")]
+pub async fn split_album(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    split_album: Json<SplitAlbum>,
+) -> AppResult<Json<Vec<String>>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let split_album = split_album.into_inner();
+    let album_id = split_album.album_id;
+
+    let (to_flush, new_album_id_list) = tokio::task::spawn_blocking(
+        move || -> Result<(Vec<AbstractData>, Vec<ArrayString<64>>)> {
+            let mut album = read_album(album_id)?;
+            let mut member_list = read_members(album_id)?;
+            member_list.sort_by_key(|(_, timestamp)| *timestamp);
+
+            let group_list = group_members(member_list, split_album.split_by);
+            if group_list.len() < 2 {
+                bail!("Album '{}' does not span more than one group", album_id);
+            }
+
+            let mut to_flush = Vec::new();
+            let mut new_album_id_list = Vec::new();
+            for (label, members) in group_list {
+                let new_album_id = generate_random_hash();
+                let mut new_album = Album::new(
+                    new_album_id,
+                    Some(match &album.title {
+                        Some(title) => format!("{} – {}", title, label),
+                        None => label,
+                    }),
+                );
+                new_album.parent_id = Some(album_id);
+                to_flush.push(AbstractData::Album(new_album));
+                for mut database in members {
+                    database.album.remove(&album_id);
+                    database.album.insert(new_album_id);
+                    to_flush.push(AbstractData::Database(database));
+                }
+                new_album_id_list.push(new_album_id);
+            }
+
+            album.include_children = true;
+            to_flush.push(AbstractData::Album(album));
+            Ok((to_flush, new_album_id_list))
+        },
+    )
+    .await??;
+
+    let mut refresh_album_list = new_album_id_list.clone();
+    refresh_album_list.push(album_id);
+    flush_and_refresh(to_flush, Vec::new(), refresh_album_list).await?;
+    Ok(Json(
+        new_album_id_list
+            .into_iter()
+            .map(|album_id| album_id.to_string())
+            .collect(),
+    ))
+}
+
+fn read_album(album_id: ArrayString<64>) -> Result<Album> {
+    Ok(open_album_table()
+        .get(&*album_id)?
+        .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?
+        .value())
+}
+
+/// Full records of the items directly in an album with their timeline timestamps; the in-memory
+/// tree only keeps trimmed copies.
+fn read_members(album_id: ArrayString<64>) -> Result<Vec<(Database, u128)>> {
+    let hash_list: Vec<_> = TREE
+        .in_memory
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(
+            |database_timestamp| match &database_timestamp.abstract_data {
+                AbstractData::Database(database) if database.album.contains(&album_id) => {
+                    Some((database.hash, database_timestamp.timestamp))
+                }
+                _ => None,
+            },
+        )
+        .collect();
+    let data_table = open_data_table()?;
+    let mut member_list = Vec::with_capacity(hash_list.len());
+    for (hash, timestamp) in hash_list {
+        if let Some(guard) = data_table.get(&*hash)? {
+            member_list.push((guard.value(), timestamp));
+        }
+    }
+    Ok(member_list)
+}
+
+fn group_members(
+    member_list: Vec<(Database, u128)>,
+    split_by: SplitBy,
+) -> Vec<(String, Vec<Database>)> {
+    let day_label = |timestamp: u128| {
+        Local
+            .timestamp_millis_opt(timestamp as i64)
+            .single()
+            .map(|datetime| datetime.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    match split_by {
+        SplitBy::Day => {
+            let mut group_map: BTreeMap<String, Vec<Database>> = BTreeMap::new();
+            for (database, timestamp) in member_list {
+                group_map
+                    .entry(day_label(timestamp))
+                    .or_default()
+                    .push(database);
+            }
+            group_map.into_iter().collect()
+        }
+        SplitBy::Gap { gap_hours } => {
+            let gap = Duration::from_secs(gap_hours * 60 * 60).as_millis();
+            let mut group_list: Vec<(String, Vec<Database>)> = Vec::new();
+            let mut last_timestamp_opt: Option<u128> = None;
+            for (database, timestamp) in member_list {
+                let starts_group = last_timestamp_opt
+                    .is_none_or(|last_timestamp| timestamp.saturating_sub(last_timestamp) > gap);
+                if starts_group {
+                    group_list.push((day_label(timestamp), Vec::new()));
+                }
+                group_list.last_mut().unwrap().1.push(database);
+                last_timestamp_opt = Some(timestamp);
+            }
+            group_list
+        }
+    }
+}
+
+async fn flush_and_refresh(
+    to_flush: Vec<AbstractData>,
+    to_remove: Vec<AbstractData>,
+    mut refresh_album_list: Vec<ArrayString<64>>,
+) -> Result<()> {
+    if !to_remove.is_empty() {
+        // Removed albums are refreshed too, which clears what is left pointing at them
+        refresh_album_list.extend(to_remove.iter().map(AbstractData::hash));
+        BATCH_COORDINATOR
+            .execute_batch_waiting(FlushTreeTask::remove(to_remove))
+            .await?;
+    }
+    BATCH_COORDINATOR
+        .execute_batch_waiting(FlushTreeTask::insert(to_flush))
+        .await?;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+
+    const ALBUM_CONC: usize = 8;
+    stream::iter(refresh_album_list)
+        .map(|album_id| async move {
+            INDEX_COORDINATOR
+                .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+                .await
+        })
+        .buffer_unordered(ALBUM_CONC)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(())
+}
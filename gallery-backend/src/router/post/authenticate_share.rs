@@ -0,0 +1,191 @@
+use anyhow::{Result, anyhow};
+use arrayvec::ArrayString;
+use rocket::http::Status;
+use rocket::post;
+use rocket::serde::json::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::operations::open_db::open_album_table;
+use crate::public::structure::album::share_limit::record_share_view;
+use crate::router::claims::claims_share::ClaimsShare;
+use crate::router::fairing::auth_utils::find_share;
+use crate::router::fairing::guard_client_addr::GuardClientAddr;
+use crate::router::{AppError, AppResult};
+
+/// Failed attempts allowed per client and share within `LOCKOUT_WINDOW`.
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+/// Failed attempts allowed per share from all clients together, against attackers that spread
+/// their guesses over many addresses. Only clients that have failed themselves are turned away,
+/// so viewers who know the password can still get in.
+const MAX_FAILED_ATTEMPTS_PER_SHARE: u32 = 50;
+const LOCKOUT_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+type ClientShare = (IpAddr, ArrayString<64>);
+
+/// `(failed attempts, first failure)` per key.
+type AttemptCounter<K> = HashMap<K, (u32, Instant)>;
+
+#[derive(Default)]
+struct FailedAttempts {
+    by_client: AttemptCounter<ClientShare>,
+    by_share: AttemptCounter<ArrayString<64>>,
+}
+
+static FAILED_ATTEMPTS: LazyLock<Mutex<FailedAttempts>> =
+    LazyLock::new(|| Mutex::new(FailedAttempts::default()));
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticateShare {
+    pub album_id: ArrayString<64>,
+    pub share_id: ArrayString<64>,
+    pub password: String,
+}
+
+/// Exchange a share password for a token to send as `x-share-token` (or `shareToken`).
+///
+/// Each token counts as one view of the share. Shares without a password take any password.
+/// Unknown shares and wrong passwords get the same answer.
+#[post("/post/authenticate_share", data = "<authenticate_share>")]
+pub async fn authenticate_share(
+    client_addr: GuardClientAddr,
+    authenticate_share: Json<AuthenticateShare>,
+) -> AppResult<Json<String>> {
+    let authenticate_share = authenticate_share.into_inner();
+    let album_id = authenticate_share.album_id;
+    let share_id = authenticate_share.share_id;
+    let client_share = (
+        client_addr.0.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        share_id,
+    );
+    // Counted as failed up front so concurrent guesses cannot all pass the check
+    if !begin_attempt(&client_share) {
+        return Err(AppError {
+            status: Status::TooManyRequests,
+            error: anyhow!("Too many failed attempts; try again later"),
+        });
+    }
+
+    let verdict = tokio::task::spawn_blocking(move || -> Result<Verdict> {
+        let Some((album, share)) = find_share(&open_album_table(), &album_id, &share_id)? else {
+            return Ok(Verdict::Denied);
+        };
+        if !share.verify_password(&authenticate_share.password) {
+            return Ok(Verdict::Denied);
+        }
+        if share.is_expired() || !record_share_view(album.id, share_id)? {
+            return Ok(Verdict::Gone);
+        }
+        Ok(Verdict::Granted(ClaimsShare::new(
+            album.id, share_id, &share,
+        )))
+    })
+    .await?;
+
+    match verdict {
+        Ok(Verdict::Granted(claims)) => {
+            forgive_attempt(&client_share);
+            Ok(Json(claims.encode()))
+        }
+        Ok(Verdict::Denied) => Err(AppError {
+            status: Status::Unauthorized,
+            error: anyhow!("Invalid share link or password"),
+        }),
+        Ok(Verdict::Gone) => {
+            forgive_attempt(&client_share);
+            Err(AppError {
+                status: Status::Gone,
+                error: anyhow!("Share has expired or reached its view limit"),
+            })
+        }
+        Err(err) => {
+            forgive_attempt(&client_share);
+            Err(err.into())
+        }
+    }
+}
+
+enum Verdict {
+    /// Password accepted and the view counted; carries the claims for the album the share
+    /// belongs to now.
+    Granted(ClaimsShare),
+    /// Unknown share or wrong password, which are not told apart.
+    Denied,
+    /// Right password, but the share has expired or its views are used up.
+    Gone,
+}
+
+/// Record an attempt as failed, unless the client is locked out.
+fn begin_attempt(client_share: &ClientShare) -> bool {
+    let mut failed_attempts = FAILED_ATTEMPTS.lock().unwrap();
+    let FailedAttempts {
+        by_client,
+        by_share,
+    } = &mut *failed_attempts;
+    let now = Instant::now();
+    // Forget windows that have passed so the maps do not grow without bound
+    by_client.retain(|_, (_, first_failure)| now.duration_since(*first_failure) <= LOCKOUT_WINDOW);
+    by_share.retain(|_, (_, first_failure)| now.duration_since(*first_failure) <= LOCKOUT_WINDOW);
+    let client_failures = failure_count(by_client, client_share);
+    if client_failures >= MAX_FAILED_ATTEMPTS
+        || (client_failures > 0
+            && failure_count(by_share, &client_share.1) >= MAX_FAILED_ATTEMPTS_PER_SHARE)
+    {
+        return false;
+    }
+    by_client.entry(*client_share).or_insert((0, now)).0 += 1;
+    by_share.entry(client_share.1).or_insert((0, now)).0 += 1;
+    true
+}
+
+/// Take back the failure recorded by [`begin_attempt`] once the password turned out right.
+fn forgive_attempt(client_share: &ClientShare) {
+    let mut failed_attempts = FAILED_ATTEMPTS.lock().unwrap();
+    failed_attempts.by_client.remove(client_share);
+    if let Some((count, _)) = failed_attempts.by_share.get_mut(&client_share.1) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+fn failure_count<K: Eq + Hash>(counter: &AttemptCounter<K>, key: &K) -> u32 {
+    counter.get(key).map_or(0, |(count, _)| *count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_share(last_octet: u8, share_id: &str) -> ClientShare {
+        (
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, last_octet)),
+            ArrayString::from(share_id).unwrap(),
+        )
+    }
+
+    #[test]
+    fn busy_share_still_admits_clients_without_failures() {
+        for last_octet in 0..MAX_FAILED_ATTEMPTS_PER_SHARE as u8 {
+            assert!(begin_attempt(&client_share(last_octet, "busy")));
+        }
+        let failed_before = client_share(0, "busy");
+        assert!(!begin_attempt(&failed_before));
+        let newcomer = client_share(200, "busy");
+        assert!(begin_attempt(&newcomer));
+        forgive_attempt(&newcomer);
+    }
+
+    #[test]
+    fn client_is_locked_out_after_repeated_failures() {
+        let client = client_share(1, "guessed");
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert!(begin_attempt(&client));
+        }
+        assert!(!begin_attempt(&client));
+        assert!(begin_attempt(&client_share(2, "guessed")));
+    }
+}
@@ -23,7 +23,13 @@ pub struct CreateShare {
     pub show_metadata: bool,
     pub show_download: bool,
     pub show_upload: bool,
-    pub exp: u64,
+    /// Unix time in seconds after which the link stops working; `0` never expires.
+    #[serde(default)]
+    pub expires_at: u64,
+    #[serde(default)]
+    pub max_views: Option<u64>,
+    #[serde(default)]
+    pub include_children: bool,
 }
 
 #[post("/post/create_share", data = "<create_share>")]
@@ -66,18 +72,25 @@ fn create_and_insert_share(txn: &WriteTransaction, create_share: CreateShare) ->
                 .map(char::from)
                 .collect();
             let share_id = ArrayString::<64>::from(&link).unwrap();
-            let share = Share {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let mut share = Share {
                 url: share_id,
                 description: create_share.description,
-                password: create_share.password,
+                password: None,
                 show_metadata: create_share.show_metadata,
                 show_download: create_share.show_download,
                 show_upload: create_share.show_upload,
-                exp: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                exp: create_share.expires_at,
+                created_at: now,
+                max_views: create_share.max_views,
+                view_count: 0,
+                include_children: create_share.include_children,
+                password_version: 0,
             };
+            share.set_password(create_share.password.as_deref())?;
             album.share_list.insert(share_id, share);
             album_table.insert(&*create_share.album_id, album).unwrap();
             Ok(link)
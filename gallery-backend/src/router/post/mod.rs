@@ -1,12 +1,18 @@
 use rocket::Route;
+pub mod album_operations;
 pub mod authenticate;
+pub mod authenticate_share;
 pub mod create_album;
 pub mod create_share;
 pub mod post_upload;
 
 pub fn generate_post_routes() -> Vec<Route> {
     routes![
+        album_operations::duplicate_album,
+        album_operations::merge_albums,
+        album_operations::split_album,
         authenticate::authenticate,
+        authenticate_share::authenticate_share,
         create_album::create_non_empty_album,
         create_album::create_empty_album,
         post_upload::upload,
@@ -0,0 +1,63 @@
+use crate::operations::indexation::apply_tag_rules::TagRules;
+use crate::operations::open_db::open_data_table;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use futures::future::try_join_all;
+use redb::ReadableTable;
+use rocket::serde::json::Json;
+use std::collections::HashSet;
+
+/// Re-run the `autoTag` rules over the whole library; returns how many items gained tags or albums.
+#[post("/put/apply-tag-rules")]
+pub async fn apply_tag_rules_to_library(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+) -> AppResult<Json<usize>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let (to_flush, affected_album_ids) = tokio::task::spawn_blocking(
+        || -> Result<(Vec<AbstractData>, HashSet<ArrayString<64>>)> {
+            let tag_rules = TagRules::load();
+            let data_table = open_data_table()?;
+            let mut to_flush = Vec::new();
+            let mut affected_album_ids = HashSet::new();
+            for entry in data_table.iter()? {
+                let (_, guard) = entry?;
+                let mut database = guard.value();
+                let album_before = database.album.clone();
+                if tag_rules.apply(&mut database) {
+                    affected_album_ids.extend(database.album.difference(&album_before).copied());
+                    to_flush.push(AbstractData::Database(database));
+                }
+            }
+            Ok((to_flush, affected_album_ids))
+        },
+    )
+    .await??;
+
+    let updated_count = to_flush.len();
+    if updated_count > 0 {
+        BATCH_COORDINATOR
+            .execute_batch_waiting(FlushTreeTask::insert(to_flush))
+            .await?;
+        BATCH_COORDINATOR
+            .execute_batch_waiting(UpdateTreeTask)
+            .await?;
+        try_join_all(affected_album_ids.into_iter().map(|album_id| async move {
+            INDEX_COORDINATOR
+                .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+                .await
+        }))
+        .await?;
+    }
+    info!("Auto-tag rules updated {} items", updated_count);
+    Ok(Json(updated_count))
+}
@@ -0,0 +1,29 @@
+use crate::router::AppResult;
+use crate::router::GuardResult;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::tasks::batcher::collect_garbage::{GcReport, sweep_garbage};
+use anyhow::Result;
+use rocket::serde::json::Json;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CollectGarbageData {
+    /// Only report what would be reclaimed.
+    dry_run: bool,
+}
+
+/// Sweep the object storage for files no item refers to and report what was reclaimed.
+#[post("/put/collect-garbage", format = "json", data = "<json_data>")]
+pub async fn collect_garbage(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<CollectGarbageData>,
+) -> AppResult<Json<GcReport>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let dry_run = json_data.into_inner().dry_run;
+    let report = tokio::task::spawn_blocking(move || sweep_garbage(dry_run)).await??;
+    Ok(Json(report))
+}
@@ -2,6 +2,9 @@ use crate::operations::open_db::{open_data_table, open_tree_snapshot_table};
 use crate::process::transitor::index_to_database;
 use crate::public::constant::redb::{ALBUM_TABLE, DATA_TABLE};
 use crate::public::db::tree::TREE;
+use crate::public::db::tree::album_tree::creates_cycle;
+use crate::public::storage::STORAGE;
+use crate::public::structure::album::{AlbumSortMode, CollageCover, CollageLayout};
 use crate::router::fairing::guard_auth::GuardAuth;
 use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
 use crate::router::fairing::guard_share::GuardShare;
@@ -10,7 +13,7 @@ use crate::tasks::actor::album::AlbumSelfUpdateTask;
 use crate::tasks::batcher::flush_tree::FlushTreeTask;
 use crate::tasks::batcher::update_tree::UpdateTreeTask;
 use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use arrayvec::ArrayString;
 use futures::{StreamExt, TryStreamExt, stream};
 use redb::ReadableTable;
@@ -171,3 +174,180 @@ pub async fn set_album_title(
 
     Ok(())
 }
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAlbumParent {
+    pub album_id: ArrayString<64>,
+    /// `None` moves the album back to the top level.
+    pub parent_id: Option<ArrayString<64>>,
+    /// Left unchanged when absent.
+    pub include_children: Option<bool>,
+}
+
+/// File an album under another one, turning the latter into a folder.
+#[post("/post/set_album_parent", data = "<set_album_parent>")]
+pub async fn set_album_parent(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    set_album_parent: Json<SetAlbumParent>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let refresh_album_vec = tokio::task::spawn_blocking(move || -> Result<Vec<ArrayString<64>>> {
+        let set_album_parent_inner = set_album_parent.into_inner();
+        let album_id = set_album_parent_inner.album_id;
+
+        let txn = TREE.in_disk.begin_write()?;
+        let old_parent_id_opt;
+        {
+            let mut album_table = txn.open_table(ALBUM_TABLE)?;
+            let mut album = album_table
+                .get(&*album_id)?
+                .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?
+                .value();
+
+            if creates_cycle(album_id, set_album_parent_inner.parent_id, |ancestor_id| {
+                Ok(album_table
+                    .get(&*ancestor_id)?
+                    .ok_or_else(|| anyhow!("Album not found for id '{}'", ancestor_id))?
+                    .value()
+                    .parent_id)
+            })? {
+                bail!("Album '{}' cannot be nested inside itself", album_id);
+            }
+
+            old_parent_id_opt = album.parent_id;
+            album.parent_id = set_album_parent_inner.parent_id;
+            if let Some(include_children) = set_album_parent_inner.include_children {
+                album.include_children = include_children;
+            }
+            album_table.insert(&*album_id, album)?;
+        }
+        txn.commit()?;
+
+        // Refreshing the album also refreshes its new ancestors
+        Ok(std::iter::once(album_id).chain(old_parent_id_opt).collect())
+    })
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    for album_id in refresh_album_vec {
+        INDEX_COORDINATOR
+            .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+            .await??;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAlbumSortMode {
+    pub album_id: ArrayString<64>,
+    pub sort_mode: AlbumSortMode,
+}
+
+#[post("/post/set_album_sort_mode", data = "<set_album_sort_mode>")]
+pub async fn set_album_sort_mode(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    set_album_sort_mode: Json<SetAlbumSortMode>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let set_album_sort_mode_inner = set_album_sort_mode.into_inner();
+        let album_id = set_album_sort_mode_inner.album_id;
+
+        let txn = TREE.in_disk.begin_write()?;
+        {
+            let mut album_table = txn.open_table(ALBUM_TABLE)?;
+            let mut album = album_table
+                .get(&*album_id)?
+                .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?
+                .value();
+
+            // The custom order is kept so switching back restores it
+            album.sort_mode = set_album_sort_mode_inner.sort_mode;
+            album_table.insert(&*album_id, album)?;
+        }
+        txn.commit()?;
+        Ok(())
+    })
+    .await??;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAlbumCollage {
+    pub album_id: ArrayString<64>,
+    /// `None` goes back to the single `cover`.
+    pub layout: Option<CollageLayout>,
+    /// Items to place first; empty lets the album pick favorites and its earliest items.
+    #[serde(default)]
+    pub hash_array: Vec<ArrayString<64>>,
+}
+
+/// Use a generated collage of several items as the album cover.
+#[post("/post/set_album_collage", data = "<set_album_collage>")]
+pub async fn set_album_collage(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    set_album_collage: Json<SetAlbumCollage>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let set_album_collage_inner = set_album_collage.into_inner();
+    let album_id = set_album_collage_inner.album_id;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let txn = TREE.in_disk.begin_write()?;
+        let mut stale_image_opt = None;
+        {
+            let mut album_table = txn.open_table(ALBUM_TABLE)?;
+            let data_table = txn.open_table(DATA_TABLE)?;
+            let mut album = album_table
+                .get(&*album_id)?
+                .ok_or_else(|| anyhow!("Album not found for id '{}'", album_id))?
+                .value();
+
+            let old_image = album.collage.take().and_then(|collage| collage.image);
+            match set_album_collage_inner.layout {
+                Some(layout) => {
+                    let mut collage = CollageCover::new(layout, set_album_collage_inner.hash_array);
+                    // Replaced by the album update once the new collage is drawn
+                    collage.image = old_image;
+                    album.collage = Some(collage);
+                }
+                None => {
+                    stale_image_opt = old_image;
+                    album.thumbhash = None;
+                    if let Some(cover_hash) = album.cover
+                        && let Some(guard) = data_table.get(&*cover_hash)?
+                    {
+                        album.set_cover(&guard.value());
+                    }
+                }
+            }
+            album_table.insert(&*album_id, album)?;
+        }
+        txn.commit()?;
+        if let Some(stale_image) = stale_image_opt {
+            STORAGE.delete(&CollageCover::image_key(&stale_image))?;
+        }
+        Ok(())
+    })
+    .await??;
+    INDEX_COORDINATOR
+        .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+        .await??;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
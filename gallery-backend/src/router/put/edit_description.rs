@@ -0,0 +1,61 @@
+use crate::operations::open_db::{open_data_and_album_tables, open_tree_snapshot_table};
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::operations::utils::xmp_sidecar::write_sidecar;
+use crate::process::transitor::index_to_abstract_data;
+use crate::public::error_data::handle_error;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::Result;
+use rocket::serde::{Deserialize, json::Json};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditDescriptionData {
+    index_array: Vec<usize>,
+    /// An empty string clears the caption.
+    description: String,
+    timestamp: u128,
+}
+
+/// Set the caption of items and mirror it into their XMP sidecars.
+#[put("/put/edit_description", format = "json", data = "<json_data>")]
+pub async fn edit_description(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<EditDescriptionData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let (data_table, album_table) = open_data_and_album_tables();
+        let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+        let description = Some(json_data.description.trim().to_string())
+            .filter(|description| !description.is_empty());
+        let now = get_current_timestamp_u64() as u128;
+
+        for &index in &json_data.index_array {
+            let mut abstract_data =
+                index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
+            if let AbstractData::Database(database) = &mut abstract_data {
+                database.description = description.clone();
+                database.metadata_edited_at = Some(now);
+                if let Err(err) = write_sidecar(database) {
+                    handle_error(err.context("Failed to write XMP sidecar"));
+                }
+                BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(vec![abstract_data]))
+            }
+        }
+        Ok(())
+    })
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use crate::operations::open_db::{open_data_and_album_tables, open_tree_snapshot_table};
+use crate::process::transitor::index_to_abstract_data;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::Result;
+use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditMetadataData {
+    index_array: Vec<usize>,
+    /// Keys to set; their previous values are replaced.
+    #[serde(default)]
+    set_metadata: HashMap<String, Vec<String>>,
+    /// Keys to drop, applied before `set_metadata`.
+    #[serde(default)]
+    remove_keys_array: Vec<String>,
+    timestamp: u128,
+}
+
+/// Edit the free-form key/value metadata of items and albums.
+#[put("/put/edit_metadata", format = "json", data = "<json_data>")]
+pub async fn edit_metadata(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<EditMetadataData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let (data_table, album_table) = open_data_and_album_tables();
+        let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+
+        for &index in &json_data.index_array {
+            let mut abstract_data =
+                index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
+
+            let metadata = abstract_data.user_defined_metadata_mut();
+            for key in &json_data.remove_keys_array {
+                metadata.remove(key);
+            }
+            for (key, values) in &json_data.set_metadata {
+                metadata.insert(key.clone(), values.clone());
+            }
+
+            BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(vec![abstract_data]))
+        }
+        Ok(())
+    })
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::operations::open_db::{open_data_and_album_tables, open_tree_snapshot_table};
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::operations::utils::xmp_sidecar::write_sidecar;
+use crate::process::transitor::index_to_abstract_data;
+use crate::public::error_data::handle_error;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::Result;
+use rocket::serde::{Deserialize, json::Json};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRatingData {
+    index_array: Vec<usize>,
+    timestamp: u128,
+    /// 0 to 5 stars; left unchanged when absent.
+    rating: Option<u8>,
+    /// Colour label; an empty string clears it, absent leaves it unchanged.
+    label: Option<String>,
+}
+
+/// Set the star rating and/or colour label of items and mirror them into their XMP sidecars.
+#[put("/put/edit_rating", format = "json", data = "<json_data>")]
+pub async fn edit_rating(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<EditRatingData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let to_flush = tokio::task::spawn_blocking(move || -> Result<Vec<AbstractData>> {
+        let (data_table, album_table) = open_data_and_album_tables();
+        let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+
+        let now = get_current_timestamp_u64() as u128;
+        let mut to_flush = Vec::with_capacity(json_data.index_array.len());
+        for &index in &json_data.index_array {
+            let mut abstract_data =
+                index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
+            // Albums carry no rating of their own
+            if let AbstractData::Database(database) = &mut abstract_data {
+                database.metadata_edited_at = Some(now);
+                if let Some(rating) = json_data.rating {
+                    database.rating = rating.min(5);
+                }
+                if let Some(label) = &json_data.label {
+                    database.label =
+                        Some(label.trim().to_string()).filter(|label| !label.is_empty());
+                }
+                if let Err(err) = write_sidecar(database) {
+                    handle_error(err.context("Failed to write XMP sidecar"));
+                }
+                to_flush.push(abstract_data);
+            }
+        }
+        Ok(to_flush)
+    })
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(FlushTreeTask::insert(to_flush))
+        .await?;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use crate::public::db::tree::TREE;
+use crate::public::db::tree::share_access::remove_share_records;
 use crate::public::structure::album::Share;
+use crate::public::structure::album::share_limit::remove_shares;
 use crate::router::GuardResult;
 use crate::router::fairing::guard_auth::GuardAuth;
 use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
@@ -10,6 +12,7 @@ use anyhow::Result;
 use arrayvec::ArrayString;
 use redb::ReadableTable;
 use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashSet;
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EditShare {
@@ -25,7 +28,7 @@ pub async fn edit_share(
 ) -> AppResult<()> {
     let _ = auth?;
     let _ = read_only_mode?;
-    tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(move || -> Result<()> {
         let txn = TREE.in_disk.begin_write().unwrap();
         {
             let mut album_table = txn.open_table(ALBUM_TABLE).unwrap();
@@ -36,18 +39,30 @@ pub async fn edit_share(
                 .map(|guard| guard.value());
 
             if let Some(mut album) = album_opt {
-                album
-                    .share_list
-                    .insert(json_data.share.url, json_data.share.clone());
+                let mut share = json_data.share.clone();
+                let stored_opt = album.share_list.get(&share.url);
+                let stored_password = stored_opt.and_then(|stored| stored.password.clone());
+                // Counters are kept by the server, not the client
+                if let Some(stored) = stored_opt {
+                    share.created_at = stored.created_at;
+                    share.view_count = stored.view_count;
+                    share.password_version = stored.password_version;
+                }
+                // Clients send back the hash they were given when the password is unchanged
+                if share.password != stored_password {
+                    let password = share.password.take();
+                    share.set_password(password.as_deref())?;
+                }
+                album.share_list.insert(share.url, share);
                 album_table
                     .insert(json_data.album_id.as_str(), &album)
                     .unwrap();
             }
         }
         txn.commit().unwrap();
+        Ok(())
     })
-    .await
-    .unwrap();
+    .await??;
     BATCH_COORDINATOR
         .execute_batch_waiting(UpdateTreeTask)
         .await
@@ -70,7 +85,7 @@ pub async fn delete_share(
 ) -> AppResult<()> {
     let _ = auth?;
     let _ = read_only_mode?;
-    tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(move || -> Result<()> {
         let txn = TREE.in_disk.begin_write().unwrap();
         {
             let mut album_table = txn.open_table(ALBUM_TABLE).unwrap();
@@ -87,13 +102,42 @@ pub async fn delete_share(
                     .unwrap();
             }
         }
+        remove_share_records(&txn, &HashSet::from([json_data.share_id]))?;
         txn.commit().unwrap();
+        Ok(())
     })
-    .await
-    .unwrap();
+    .await??;
     BATCH_COORDINATOR
         .execute_batch_waiting(UpdateTreeTask)
         .await
         .unwrap();
     Ok(())
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeAllShares {
+    /// Only this album's shares; every album's when absent.
+    #[serde(default)]
+    album_id: Option<ArrayString<64>>,
+}
+
+/// Delete every share link; returns how many were revoked.
+#[put("/put/revoke_all_shares", format = "json", data = "<json_data>")]
+pub async fn revoke_all_shares(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<RevokeAllShares>,
+) -> AppResult<Json<usize>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let album_id_opt = json_data.into_inner().album_id;
+    let revoked_count = tokio::task::spawn_blocking(move || {
+        remove_shares(|album_id, _| album_id_opt.is_none_or(|target| target == album_id))
+    })
+    .await??;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(Json(revoked_count))
+}
@@ -2,15 +2,24 @@ use crate::operations::open_db::{open_data_and_album_tables, open_tree_snapshot_
 use crate::process::transitor::index_to_abstract_data;
 use crate::public::db::tree_snapshot::TREE_SNAPSHOT;
 
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::operations::utils::xmp_sidecar::write_sidecar;
 use crate::public::db::tree::read_tags::TagInfo;
+use crate::public::error_data::handle_error;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::flag::Flag;
 use crate::router::fairing::guard_auth::GuardAuth;
 use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
 use crate::router::{AppResult, GuardResult};
-use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
 use crate::tasks::batcher::flush_tree::FlushTreeTask;
 use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
 use anyhow::Result;
+use arrayvec::ArrayString;
+use futures::future::try_join_all;
 use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashSet;
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EditTagsData {
@@ -27,29 +36,42 @@ pub async fn edit_tag(
 ) -> AppResult<Json<Vec<TagInfo>>> {
     let _ = auth?;
     let _ = read_only_mode?;
-    let vec_tags_info = tokio::task::spawn_blocking(move || -> Result<Vec<TagInfo>> {
-        let (data_table, album_table) = open_data_and_album_tables();
-        let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+    let is_trash_edit = json_data
+        .add_tags_array
+        .iter()
+        .chain(&json_data.remove_tags_array)
+        .any(|tag| tag == Flag::Trashed.tag());
+    let (vec_tags_info, affected_album_ids) = tokio::task::spawn_blocking(
+        move || -> Result<(Vec<TagInfo>, HashSet<ArrayString<64>>)> {
+            let (data_table, album_table) = open_data_and_album_tables();
+            let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+            let now = get_current_timestamp_u64() as u128;
+            let mut affected_album_ids = HashSet::new();
 
-        for &index in &json_data.index_array {
-            let mut abstract_data =
-                index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
+            for &index in &json_data.index_array {
+                let mut abstract_data =
+                    index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
 
-            let tag_set = abstract_data.tag_mut();
+                // Apply tag additions and removals in one pass
+                for tag in &json_data.add_tags_array {
+                    abstract_data.edit_tag(tag, true, now);
+                }
+                for tag in &json_data.remove_tags_array {
+                    abstract_data.edit_tag(tag, false, now);
+                }
+                if let AbstractData::Database(database) = &abstract_data {
+                    affected_album_ids.extend(database.album.iter().cloned());
+                    if let Err(err) = write_sidecar(database) {
+                        handle_error(err.context("Failed to write XMP sidecar"));
+                    }
+                }
 
-            // Apply tag additions and removals in one pass
-            for tag in &json_data.add_tags_array {
-                tag_set.insert(tag.clone());
+                BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(vec![abstract_data]))
             }
-            for tag in &json_data.remove_tags_array {
-                tag_set.remove(tag);
-            }
-
-            BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(vec![abstract_data]))
-        }
 
-        Ok(TREE_SNAPSHOT.read_tags()?)
-    })
+            Ok((TREE_SNAPSHOT.read_tags()?, affected_album_ids))
+        },
+    )
     .await
     .unwrap()?;
 
@@ -58,5 +80,15 @@ pub async fn edit_tag(
         .await
         .unwrap();
 
+    // Trashed items no longer count towards their albums
+    if is_trash_edit {
+        try_join_all(affected_album_ids.into_iter().map(|album_id| async move {
+            INDEX_COORDINATOR
+                .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+                .await
+        }))
+        .await?;
+    }
+
     Ok(Json(vec_tags_info))
 }
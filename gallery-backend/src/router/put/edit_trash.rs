@@ -0,0 +1,88 @@
+use crate::operations::open_db::{open_data_and_album_tables, open_tree_snapshot_table};
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::process::transitor::index_to_abstract_data;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::flag::Flag;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use futures::future::try_join_all;
+use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashSet;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashData {
+    index_array: Vec<usize>,
+    timestamp: u128,
+}
+
+/// Move items or albums to the trash; they are purged after `trash.retentionDays`.
+#[put("/put/trash", format = "json", data = "<json_data>")]
+pub async fn trash(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<TrashData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    set_trashed(json_data.into_inner(), true).await
+}
+
+/// Take items or albums back out of the trash.
+#[put("/put/restore", format = "json", data = "<json_data>")]
+pub async fn restore(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<TrashData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    set_trashed(json_data.into_inner(), false).await
+}
+
+async fn set_trashed(trash_data: TrashData, trashed: bool) -> AppResult<()> {
+    let (to_flush, affected_album_ids) = tokio::task::spawn_blocking(
+        move || -> Result<(Vec<AbstractData>, HashSet<ArrayString<64>>)> {
+            let (data_table, album_table) = open_data_and_album_tables();
+            let tree_snapshot = open_tree_snapshot_table(trash_data.timestamp)?;
+            let now = get_current_timestamp_u64() as u128;
+
+            let mut to_flush = Vec::with_capacity(trash_data.index_array.len());
+            let mut affected_album_ids = HashSet::new();
+            for &index in &trash_data.index_array {
+                let mut abstract_data =
+                    index_to_abstract_data(&tree_snapshot, &data_table, &album_table, index)?;
+                abstract_data.set_flag(Flag::Trashed, trashed, now);
+                if let AbstractData::Database(database) = &abstract_data {
+                    affected_album_ids.extend(database.album.iter().cloned());
+                }
+                to_flush.push(abstract_data);
+            }
+            Ok((to_flush, affected_album_ids))
+        },
+    )
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(FlushTreeTask::insert(to_flush))
+        .await?;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+
+    // Trashed items no longer count towards their albums
+    try_join_all(affected_album_ids.into_iter().map(|album_id| async move {
+        INDEX_COORDINATOR
+            .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+            .await
+    }))
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use crate::operations::hash::generate_random_hash;
+use crate::operations::open_db::open_data_table;
+use crate::public::constant::redb::EVENT_PROPOSAL_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::album::Album;
+use crate::public::structure::event_proposal::{EventProposal, ProposalStatus};
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::cluster_events::ClusterEventsTask;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
+use anyhow::{Result, anyhow};
+use arrayvec::ArrayString;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptEventProposal {
+    pub proposal_id: ArrayString<64>,
+    /// Defaults to the proposed title.
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectEventProposal {
+    pub proposal_id: ArrayString<64>,
+}
+
+/// Turn a proposal into an album holding its items; returns the new album id.
+#[post("/put/accept-event-proposal", data = "<accept_event_proposal>")]
+pub async fn accept_event_proposal(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    accept_event_proposal: Json<AcceptEventProposal>,
+) -> AppResult<String> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let accept_event_proposal = accept_event_proposal.into_inner();
+    let album_id = generate_random_hash();
+
+    let to_flush = tokio::task::spawn_blocking(move || -> Result<Vec<AbstractData>> {
+        let proposal = read_proposal(accept_event_proposal.proposal_id)?;
+        let album = Album::new(
+            album_id,
+            Some(accept_event_proposal.title.unwrap_or(proposal.title)),
+        );
+        let mut to_flush = vec![AbstractData::Album(album)];
+        let data_table = open_data_table()?;
+        for hash in proposal.hash_list {
+            // Items deleted since clustering are simply left out
+            if let Some(guard) = data_table.get(&*hash)? {
+                let mut database = guard.value();
+                database.album.insert(album_id);
+                to_flush.push(AbstractData::Database(database));
+            }
+        }
+        Ok(to_flush)
+    })
+    .await??;
+
+    BATCH_COORDINATOR
+        .execute_batch_waiting(FlushTreeTask::insert(to_flush))
+        .await?;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    INDEX_COORDINATOR
+        .execute_waiting(AlbumSelfUpdateTask::new(album_id))
+        .await??;
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let write_txn = TREE.in_disk.begin_write()?;
+        write_txn
+            .open_table(EVENT_PROPOSAL_TABLE)?
+            .remove(&*accept_event_proposal.proposal_id)?;
+        write_txn.commit()?;
+        Ok(())
+    })
+    .await??;
+    Ok(album_id.to_string())
+}
+
+/// Dismiss a proposal; it is remembered so the same event is not proposed again.
+#[post("/put/reject-event-proposal", data = "<reject_event_proposal>")]
+pub async fn reject_event_proposal(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    reject_event_proposal: Json<RejectEventProposal>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let proposal_id = reject_event_proposal.into_inner().proposal_id;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut proposal = read_proposal(proposal_id)?;
+        proposal.status = ProposalStatus::Rejected;
+        let write_txn = TREE.in_disk.begin_write()?;
+        write_txn
+            .open_table(EVENT_PROPOSAL_TABLE)?
+            .insert(&*proposal_id, &proposal)?;
+        write_txn.commit()?;
+        Ok(())
+    })
+    .await??;
+    Ok(())
+}
+
+/// Re-cluster the timeline now instead of waiting for `events.intervalHours`.
+#[post("/put/cluster-events")]
+pub async fn cluster_events(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+) -> AppResult<Status> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    BATCH_COORDINATOR
+        .execute_batch_waiting(ClusterEventsTask)
+        .await?;
+    Ok(Status::Ok)
+}
+
+fn read_proposal(proposal_id: ArrayString<64>) -> Result<EventProposal> {
+    let read_txn = TREE.in_disk.begin_read()?;
+    let table = read_txn.open_table(EVENT_PROPOSAL_TABLE)?;
+    let proposal = table
+        .get(&*proposal_id)?
+        .map(|guard| guard.value())
+        .ok_or_else(|| anyhow!("Event proposal {} not found", proposal_id))?;
+    Ok(proposal)
+}
@@ -1,21 +1,51 @@
 use rocket::Route;
 
+pub mod apply_tag_rules;
+pub mod collect_garbage;
 pub mod edit_album;
+pub mod edit_description;
+pub mod edit_metadata;
+pub mod edit_rating;
 pub mod edit_share;
 pub mod edit_tag;
+pub mod edit_trash;
+pub mod event_proposal;
 pub mod random;
+pub mod reconcile;
 pub mod regenerate_thumbnail;
 pub mod reindex;
+pub mod rename_tag;
+pub mod reorder_album;
+pub mod verify_integrity;
 pub fn generate_put_routes() -> Vec<Route> {
     routes![
+        apply_tag_rules::apply_tag_rules_to_library,
+        collect_garbage::collect_garbage,
         edit_album::edit_album,
         edit_album::set_album_cover,
         edit_album::set_album_title,
+        edit_album::set_album_parent,
+        edit_album::set_album_sort_mode,
+        edit_album::set_album_collage,
+        edit_description::edit_description,
+        edit_metadata::edit_metadata,
+        edit_rating::edit_rating,
         edit_share::edit_share,
         edit_share::delete_share,
+        edit_share::revoke_all_shares,
         edit_tag::edit_tag,
+        edit_trash::trash,
+        edit_trash::restore,
+        event_proposal::accept_event_proposal,
+        event_proposal::reject_event_proposal,
+        event_proposal::cluster_events,
         random::generate_random_data,
+        reconcile::reconcile,
         regenerate_thumbnail::regenerate_thumbnail_with_frame,
         reindex::reindex,
+        reorder_album::reorder_album,
+        rename_tag::rename_tag,
+        rename_tag::merge_tags,
+        verify_integrity::verify_integrity,
     ]
 }
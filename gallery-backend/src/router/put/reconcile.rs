@@ -0,0 +1,20 @@
+use crate::router::AppResult;
+use crate::router::GuardResult;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::reconcile::ReconcileTask;
+use anyhow::Result;
+use rocket::http::Status;
+
+/// Rescan every sync path in the background and index whatever the watcher missed.
+#[post("/put/reconcile")]
+pub async fn reconcile(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+) -> AppResult<Status> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    BATCH_COORDINATOR.execute_batch_detached(ReconcileTask::all());
+    Ok(Status::Accepted)
+}
@@ -1,6 +1,7 @@
 use crate::operations::indexation::generate_dynamic_image::generate_dynamic_image;
 use crate::operations::indexation::generate_image_hash::{generate_phash, generate_thumbhash};
 use crate::operations::open_db::open_data_table;
+use crate::public::storage::commit_staged_file;
 use crate::public::structure::abstract_data::AbstractData;
 use crate::router::{AppResult, GuardResult};
 use crate::tasks::batcher::flush_tree::FlushTreeTask;
@@ -14,6 +15,7 @@ use anyhow::anyhow;
 use arrayvec::ArrayString;
 use rocket::form::{Errors, Form};
 use rocket::fs::TempFile;
+use std::path::Path;
 
 #[derive(FromForm, Debug)]
 pub struct RegenerateThumbnailForm<'r> {
@@ -62,6 +64,10 @@ pub async fn regenerate_thumbnail_with_frame(
         .context("Failed to copy frame file")?;
 
     let abstract_data = tokio::task::spawn_blocking(move || -> Result<AbstractData> {
+        let thumbnail_key = format!("compressed/{}/{}.jpg", &hash[0..2], hash.as_str());
+        commit_staged_file(&thumbnail_key, Path::new(&file_path))
+            .context("Failed to store frame file")?;
+
         let data_table = open_data_table()?;
         let access_guard = data_table
             .get(&*hash)
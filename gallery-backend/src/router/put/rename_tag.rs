@@ -0,0 +1,79 @@
+use crate::operations::utils::xmp_sidecar::write_sidecar;
+use crate::public::db::tree::TREE;
+use crate::public::db::tree::read_tags::TagInfo;
+use crate::public::db::tree_snapshot::TREE_SNAPSHOT;
+use crate::public::error_data::handle_error;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::Result;
+use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashSet;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameTagData {
+    old_tag: String,
+    new_tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTagsData {
+    source_tags_array: Vec<String>,
+    target_tag: String,
+}
+
+/// Rename a tag on every item and album in the library.
+#[put("/put/rename_tag", format = "json", data = "<json_data>")]
+pub async fn rename_tag(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<RenameTagData>,
+) -> AppResult<Json<Vec<TagInfo>>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let json_data = json_data.into_inner();
+    rewrite_tags(HashSet::from([json_data.old_tag]), json_data.new_tag).await
+}
+
+/// Fold several tags into one on every item and album in the library.
+#[put("/put/merge_tags", format = "json", data = "<json_data>")]
+pub async fn merge_tags(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<MergeTagsData>,
+) -> AppResult<Json<Vec<TagInfo>>> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    let json_data = json_data.into_inner();
+    rewrite_tags(
+        json_data.source_tags_array.into_iter().collect(),
+        json_data.target_tag,
+    )
+    .await
+}
+
+async fn rewrite_tags(mut from: HashSet<String>, to: String) -> AppResult<Json<Vec<TagInfo>>> {
+    let to = to.trim().to_string();
+    from.remove(&to);
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        for database in TREE.rewrite_tags(&from, &to)? {
+            if let Err(err) = write_sidecar(&database) {
+                handle_error(err.context("Failed to write XMP sidecar"));
+            }
+        }
+        Ok(())
+    })
+    .await??;
+
+    // Rebuilding the tree also bumps the version, so stale query snapshots are dropped
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+
+    let vec_tags_info = tokio::task::spawn_blocking(|| TREE_SNAPSHOT.read_tags()).await??;
+    Ok(Json(vec_tags_info))
+}
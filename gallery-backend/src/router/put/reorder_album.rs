@@ -0,0 +1,119 @@
+use crate::operations::open_db::open_tree_snapshot_table;
+use crate::public::constant::redb::ALBUM_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::album::AlbumSortMode;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::router::{AppResult, GuardResult};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::{Result, anyhow, bail};
+use arrayvec::ArrayString;
+use redb::ReadableTable;
+use rocket::serde::{Deserialize, json::Json};
+use std::collections::HashSet;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorderAlbumData {
+    album_id: ArrayString<64>,
+    /// Indices of the items to move, in the snapshot of the album view.
+    index_array: Vec<usize>,
+    /// Index of the item they are placed in front of; `None` moves them to the end.
+    before_index: Option<usize>,
+    timestamp: u128,
+}
+
+/// Move items within an album and switch the album to its custom order.
+#[put("/put/reorder_album", format = "json", data = "<json_data>")]
+pub async fn reorder_album(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<ReorderAlbumData>,
+) -> AppResult<()> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let tree_snapshot = open_tree_snapshot_table(json_data.timestamp)?;
+
+        // The snapshot holds the album as currently shown, which is the order being edited
+        let mut order = (0..tree_snapshot.len())
+            .map(|index| tree_snapshot.get_hash(index))
+            .collect::<Result<Vec<_>>>()?;
+        let mut index_array = json_data.index_array.clone();
+        index_array.sort_unstable();
+        index_array.dedup();
+        let moving = index_array
+            .iter()
+            .map(|&index| tree_snapshot.get_hash(index))
+            .collect::<Result<Vec<_>>>()?;
+        let before_hash_opt = json_data
+            .before_index
+            .map(|index| tree_snapshot.get_hash(index))
+            .transpose()?;
+
+        let member_set = album_members(json_data.album_id)?;
+        if let Some(hash) = order.iter().find(|hash| !member_set.contains(*hash)) {
+            bail!(
+                "Snapshot is not a view of album '{}': item '{}' is not in it",
+                json_data.album_id,
+                hash
+            );
+        }
+
+        let moving_set: HashSet<_> = moving.iter().copied().collect();
+        order.retain(|hash| !moving_set.contains(hash));
+        let insert_at = before_hash_opt
+            .and_then(|before_hash| order.iter().position(|hash| *hash == before_hash))
+            .unwrap_or(order.len());
+        order.splice(insert_at..insert_at, moving);
+
+        let txn = TREE.in_disk.begin_write()?;
+        {
+            let mut album_table = txn.open_table(ALBUM_TABLE)?;
+            let mut album = album_table
+                .get(&*json_data.album_id)?
+                .ok_or_else(|| anyhow!("Album not found for id '{}'", json_data.album_id))?
+                .value();
+            // A filtered view holds only some members; the rest keep their previous order after
+            // them, and items that left the album are dropped
+            let placed: HashSet<_> = order.iter().copied().collect();
+            let rest: Vec<_> = album
+                .custom_order
+                .iter()
+                .filter(|hash| member_set.contains(*hash) && !placed.contains(*hash))
+                .copied()
+                .collect();
+            order.extend(rest);
+            album.custom_order = order;
+            album.sort_mode = AlbumSortMode::Custom;
+            album_table.insert(&*json_data.album_id, album)?;
+        }
+        txn.commit()?;
+        Ok(())
+    })
+    .await??;
+
+    // Bumps the version so cached album views pick up the new order
+    BATCH_COORDINATOR
+        .execute_batch_waiting(UpdateTreeTask)
+        .await?;
+    Ok(())
+}
+
+/// Hashes of the items in `album_id`.
+fn album_members(album_id: ArrayString<64>) -> Result<HashSet<ArrayString<64>>> {
+    let tree_guard = TREE.in_memory.read().map_err(|err| anyhow!("{:?}", err))?;
+    Ok(tree_guard
+        .iter()
+        .filter_map(
+            |database_timestamp| match &database_timestamp.abstract_data {
+                AbstractData::Database(database) if database.album.contains(&album_id) => {
+                    Some(database.hash)
+                }
+                _ => None,
+            },
+        )
+        .collect())
+}
@@ -0,0 +1,31 @@
+use crate::router::AppResult;
+use crate::router::GuardResult;
+use crate::router::fairing::guard_auth::GuardAuth;
+use crate::router::fairing::guard_read_only_mode::GuardReadOnlyMode;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::verify_integrity::VerifyIntegrityTask;
+use anyhow::Result;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerifyIntegrityData {
+    /// Rebuild missing or undecodable derivatives from intact originals.
+    regenerate: bool,
+}
+
+/// Start an integrity verification in the background; poll `/get/get-integrity-report` for the result.
+#[post("/put/verify-integrity", format = "json", data = "<json_data>")]
+pub async fn verify_integrity(
+    auth: GuardResult<GuardAuth>,
+    read_only_mode: Result<GuardReadOnlyMode>,
+    json_data: Json<VerifyIntegrityData>,
+) -> AppResult<Status> {
+    let _ = auth?;
+    let _ = read_only_mode?;
+    BATCH_COORDINATOR
+        .execute_batch_detached(VerifyIntegrityTask::new(json_data.into_inner().regenerate));
+    Ok(Status::Accepted)
+}
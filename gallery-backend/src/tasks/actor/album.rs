@@ -1,11 +1,15 @@
+use crate::operations::open_db::open_album_table;
 use crate::public::constant::redb::{ALBUM_TABLE, DATA_TABLE};
 use crate::public::db::tree::TREE;
 use crate::public::error_data::handle_error;
+use crate::public::storage::STORAGE;
 use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::album::CollageCover;
+use crate::tasks::INDEX_COORDINATOR;
 use anyhow::Context;
 use anyhow::Result;
 use arrayvec::ArrayString;
-use log::info;
+use log::{info, warn};
 use mini_executor::Task;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use redb::ReadableTable;
@@ -37,10 +41,25 @@ impl Task for AlbumSelfUpdateTask {
 pub fn album_task(album_id: ArrayString<64>) -> Result<()> {
     info!("Perform album self-update");
 
+    // Drawing the collage reads thumbnails from storage, so it happens on a copy before the album
+    // table is locked
+    let rendered_opt = open_album_table()
+        .get(&*album_id)?
+        .map(|guard| guard.value())
+        .map(|mut album| {
+            album.self_update();
+            if let Err(err) = album.render_collage() {
+                handle_error(err.context("Failed to render album collage"));
+            }
+            album
+        });
+
     let txn = TREE
         .in_disk
         .begin_write()
         .context("begin_write failed (album)")?;
+    let mut parent_id_opt = None;
+    let mut stale_key_list = Vec::new();
     {
         let mut album_table = txn.open_table(ALBUM_TABLE)?;
 
@@ -53,11 +72,21 @@ pub fn album_task(album_id: ArrayString<64>) -> Result<()> {
             Some(mut album) => {
                 album.pending = true;
                 album.self_update();
+                if let Some(rendered) = rendered_opt {
+                    stale_key_list = album.adopt_collage(rendered);
+                }
                 album.pending = false;
+                parent_id_opt = album.parent_id;
                 album_table.insert(&*album_id, album).unwrap();
             }
             _ => {
                 // Album has been deleted
+                stale_key_list.extend(
+                    rendered_opt
+                        .and_then(|album| album.collage)
+                        .and_then(|collage| collage.image)
+                        .map(|image| CollageCover::image_key(&image)),
+                );
                 let ref_data = TREE.in_memory.read().unwrap();
 
                 // Collect all data contained in this album
@@ -79,9 +108,32 @@ pub fn album_task(album_id: ArrayString<64>) -> Result<()> {
                     database.album.remove(&*album_id);
                     table.insert(&*hash, database).unwrap();
                 });
+
+                // Albums filed under it move to the top level
+                let child_list: Vec<_> = album_table
+                    .iter()?
+                    .filter_map(|entry| entry.ok())
+                    .map(|(_, guard)| guard.value())
+                    .filter(|album| album.parent_id == Some(album_id))
+                    .collect();
+                for mut child in child_list {
+                    child.parent_id = None;
+                    album_table.insert(&*child.id, &child)?;
+                }
             }
         }
     }
     txn.commit().context("commit failed (album)")?;
+
+    for stale_key in stale_key_list {
+        if let Err(err) = STORAGE.delete(&stale_key) {
+            warn!("Failed to delete old collage {}: {:#}", stale_key, err);
+        }
+    }
+
+    // Folders may count this album's items too
+    if let Some(parent_id) = parent_id_opt {
+        INDEX_COORDINATOR.execute_detached(AlbumSelfUpdateTask::new(parent_id));
+    }
     Ok(())
 }
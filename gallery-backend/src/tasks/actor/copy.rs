@@ -1,14 +1,14 @@
 use anyhow::Context;
 use anyhow::Result;
 use mini_executor::Task;
-use std::fs;
 use std::sync::LazyLock;
 use tokio::sync::Semaphore;
 use tokio::task::spawn_blocking;
 
-use crate::process::io::copy_with_retry;
+use crate::public::config::SERVER_CONFIG;
 use crate::public::error_data::handle_error;
-use crate::public::structure::database_struct::database::definition::Database;
+use crate::public::storage::STORAGE;
+use crate::public::structure::database_struct::database::definition::{Database, StorageMode};
 
 static COPY_LIMIT: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::const_new(1));
 
@@ -36,21 +36,20 @@ impl Task for CopyTask {
     }
 }
 
-fn copy_task(database: Database) -> Result<Database> {
+fn copy_task(mut database: Database) -> Result<Database> {
     let source_path = database.source_path();
-    let dest_path = database.imported_path();
-
-    if let Some(parent) = dest_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create directory tree for {:?}", parent))?;
+    if SERVER_CONFIG
+        .sync
+        .config_for_file(&source_path)
+        .reference_only
+    {
+        database.storage = StorageMode::Reference;
+        return Ok(database);
     }
 
-    copy_with_retry(&source_path, &dest_path).with_context(|| {
-        format!(
-            "failed to copy file from {:?} to {:?}",
-            source_path, dest_path
-        )
-    })?; // If it fails three times, it goes into the Err branch
+    STORAGE
+        .put_file(&database.imported_key(), &source_path)
+        .with_context(|| format!("failed to store {:?}", source_path))?;
 
     Ok(database)
 }
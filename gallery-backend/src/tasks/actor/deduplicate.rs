@@ -1,10 +1,14 @@
 use crate::{
     operations::open_db::open_data_table,
     public::{
+        config::SERVER_CONFIG,
         error_data::handle_error,
         structure::{abstract_data::AbstractData, database_struct::database::definition::Database},
     },
-    tasks::{BATCH_COORDINATOR, batcher::flush_tree::FlushTreeTask},
+    tasks::{
+        BATCH_COORDINATOR,
+        batcher::{flush_tree::FlushTreeTask, update_alias::UpdateAliasTask},
+    },
 };
 use anyhow::Result;
 use arrayvec::ArrayString;
@@ -49,13 +53,29 @@ impl Task for DeduplicateTask {
 fn deduplicate_task(task: DeduplicateTask) -> Result<Option<Database>> {
     let mut database = Database::new(&task.path, task.hash)?;
 
+    // Reference-only items are read from their source, so older content at this path must not
+    // be served any more.
+    if SERVER_CONFIG
+        .sync
+        .config_for_file(&task.path)
+        .reference_only
+    {
+        BATCH_COORDINATOR
+            .execute_batch_detached(UpdateAliasTask::replaced(task.path.clone(), task.hash));
+    }
+
     let data_table = open_data_table()?;
     // File already in persistent database
 
     if let Some(guard) = data_table.get(&*database.hash).unwrap() {
         let mut database_exist = guard.value();
         let file_modify = mem::take(&mut database.alias[0]);
+        // A file that comes back to a path we marked stale replaces that alias.
+        database_exist
+            .alias
+            .retain(|alias| !(alias.is_stale() && alias.file == file_modify.file));
         database_exist.alias.push(file_modify);
+        database_exist.refresh_missing_source_tag();
         if let Some(album_id) = task.presigned_album_id_opt {
             database_exist.album.insert(album_id);
         }
@@ -3,6 +3,7 @@ use anyhow::Result;
 use anyhow::anyhow;
 use tokio_rayon::AsyncThreadPool;
 
+use crate::operations::indexation::apply_tag_rules::TagRules;
 use crate::public::constant::runtime::WORKER_RAYON_POOL;
 use crate::public::structure::abstract_data::AbstractData;
 use crate::tasks::BATCH_COORDINATOR;
@@ -93,6 +94,9 @@ fn index_task(mut database: Database) -> Result<Database> {
         database.pending = true;
     }
 
+    // Declarative `autoTag` rules see the extracted metadata
+    TagRules::load().apply(&mut database);
+
     let abstract_data = AbstractData::Database(database.clone());
     BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(vec![abstract_data]));
 
@@ -0,0 +1,253 @@
+use crate::public::config::SERVER_CONFIG;
+use crate::public::constant::redb::EVENT_PROPOSAL_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::error_data::handle_error;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::public::structure::database_struct::database::definition::Database;
+use crate::public::structure::event_proposal::{EventProposal, ProposalStatus};
+use anyhow::{Context, Result};
+use arrayvec::ArrayString;
+use chrono::{Local, TimeZone};
+use mini_executor::BatchTask;
+use redb::ReadableTable;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+static RE_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\d+(?:\.\d+)?").expect("regex compilation failure"));
+
+/// Split the timeline into events at long pauses and big jumps in location, and store the
+/// clusters not yet filed into albums as proposals.
+pub struct ClusterEventsTask;
+
+impl BatchTask for ClusterEventsTask {
+    fn batch_run(_: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            match tokio::task::spawn_blocking(cluster_events_task)
+                .await
+                .expect("blocking task panicked")
+            {
+                Ok(proposal_count) => info!("Proposed {} event albums", proposal_count),
+                Err(e) => {
+                    handle_error(e.context("Failed to cluster events"));
+                }
+            }
+        }
+    }
+}
+
+struct Point {
+    hash: ArrayString<64>,
+    timestamp: u128,
+    position: Option<(f64, f64)>,
+    in_album: bool,
+    folder: Option<String>,
+}
+
+fn cluster_events_task() -> Result<usize> {
+    let config = &SERVER_CONFIG.events;
+    let gap = Duration::from_secs(config.gap_hours * 60 * 60).as_millis();
+
+    // The tree is newest first
+    let point_list: Vec<Point> = TREE
+        .in_memory
+        .read()
+        .unwrap()
+        .iter()
+        .rev()
+        .filter_map(
+            |database_timestamp| match &database_timestamp.abstract_data {
+                AbstractData::Database(database) if !database.is_trashed() => Some(Point {
+                    hash: database.hash,
+                    timestamp: database_timestamp.timestamp,
+                    position: gps_position(database),
+                    in_album: !database.album.is_empty(),
+                    folder: folder_name(database),
+                }),
+                _ => None,
+            },
+        )
+        .collect();
+
+    let mut cluster_list: Vec<Vec<Point>> = Vec::new();
+    let mut last_timestamp = 0;
+    let mut last_position: Option<(f64, f64)> = None;
+    for point in point_list {
+        let is_far = match (last_position, point.position) {
+            (Some(last), Some(current)) => {
+                distance_km(last, current) > config.max_distance_km as f64
+            }
+            _ => false,
+        };
+        if cluster_list.is_empty() || point.timestamp.saturating_sub(last_timestamp) > gap || is_far
+        {
+            cluster_list.push(Vec::new());
+            last_position = None;
+        }
+        last_timestamp = point.timestamp;
+        last_position = point.position.or(last_position);
+        cluster_list.last_mut().unwrap().push(point);
+    }
+
+    let write_txn = TREE
+        .in_disk
+        .begin_write()
+        .context("Failed to begin write transaction")?;
+    let mut proposal_count = 0;
+    {
+        let mut proposal_table = write_txn
+            .open_table(EVENT_PROPOSAL_TABLE)
+            .context("Failed to open EVENT_PROPOSAL_TABLE")?;
+        let (rejected_list, stale_id_list): (Vec<_>, Vec<_>) = proposal_table
+            .iter()?
+            .filter_map(|entry| entry.ok())
+            .map(|(_, guard)| guard.value())
+            .partition(|proposal| proposal.status == ProposalStatus::Rejected);
+        let rejected_hash_set_list: Vec<HashSet<_>> = rejected_list
+            .iter()
+            .map(|proposal| proposal.hash_list.iter().copied().collect())
+            .collect();
+
+        // Open proposals are rebuilt from scratch on every run
+        for proposal in stale_id_list {
+            proposal_table.remove(&*proposal.id)?;
+        }
+
+        for cluster in cluster_list {
+            if cluster.len() < config.min_items {
+                continue;
+            }
+            // Mostly filed already
+            if cluster.iter().filter(|point| point.in_album).count() * 2 >= cluster.len() {
+                continue;
+            }
+            // Mostly the same as an event that was turned down
+            if rejected_hash_set_list.iter().any(|rejected| {
+                cluster
+                    .iter()
+                    .filter(|point| rejected.contains(&point.hash))
+                    .count()
+                    * 2
+                    >= cluster.len()
+            }) {
+                continue;
+            }
+
+            let proposal = build_proposal(&cluster);
+            if proposal_table.get(&*proposal.id)?.is_some() {
+                continue;
+            }
+            proposal_table.insert(&*proposal.id, &proposal)?;
+            proposal_count += 1;
+        }
+    }
+    write_txn
+        .commit()
+        .context("Failed to commit write transaction")?;
+    Ok(proposal_count)
+}
+
+fn build_proposal(cluster: &[Point]) -> EventProposal {
+    let start_time = cluster.first().unwrap().timestamp;
+    let end_time = cluster.last().unwrap().timestamp;
+
+    let position_list: Vec<_> = cluster.iter().filter_map(|point| point.position).collect();
+    let (latitude, longitude) = if position_list.is_empty() {
+        (None, None)
+    } else {
+        let count = position_list.len() as f64;
+        (
+            Some(position_list.iter().map(|(lat, _)| lat).sum::<f64>() / count),
+            Some(position_list.iter().map(|(_, lon)| lon).sum::<f64>() / count),
+        )
+    };
+
+    // Folder names such as `Kyoto 2024` are the best hint at a place we have
+    let mut folder_counts: HashMap<&str, usize> = HashMap::new();
+    for folder in cluster.iter().filter_map(|point| point.folder.as_deref()) {
+        *folder_counts.entry(folder).or_default() += 1;
+    }
+    let place_opt = folder_counts
+        .into_iter()
+        .filter(|(_, count)| count * 2 >= cluster.len())
+        .max_by_key(|(_, count)| *count)
+        .map(|(folder, _)| folder);
+
+    let (start_day, end_day) = (day_label(start_time), day_label(end_time));
+    let date = if start_day == end_day {
+        start_day
+    } else {
+        format!("{} – {}", start_day, end_day)
+    };
+    let title = match place_opt {
+        Some(place) => format!("{} · {}", place, date),
+        None => date,
+    };
+
+    EventProposal {
+        id: cluster.first().unwrap().hash,
+        title,
+        start_time,
+        end_time,
+        hash_list: cluster.iter().map(|point| point.hash).collect(),
+        latitude,
+        longitude,
+        status: ProposalStatus::Proposed,
+    }
+}
+
+fn day_label(timestamp: u128) -> String {
+    Local
+        .timestamp_millis_opt(timestamp as i64)
+        .single()
+        .map(|datetime| datetime.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn folder_name(database: &Database) -> Option<String> {
+    let file_modify = database
+        .alias
+        .iter()
+        .filter(|file_modify| !file_modify.is_stale())
+        .max()?;
+    Path::new(&file_modify.file)
+        .parent()?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Decimal degrees from EXIF values such as `35 deg 39 min 15.39 sec` and their `N`/`S`/`E`/`W` refs.
+fn gps_position(database: &Database) -> Option<(f64, f64)> {
+    let coordinate = |value_key: &str, ref_key: &str, negative: char| -> Option<f64> {
+        let numbers: Vec<f64> = RE_NUMBER
+            .find_iter(database.exif_vec.get(value_key)?)
+            .filter_map(|number| number.as_str().parse().ok())
+            .collect();
+        let degrees = numbers.first()?
+            + numbers.get(1).unwrap_or(&0.0) / 60.0
+            + numbers.get(2).unwrap_or(&0.0) / 3600.0;
+        let is_negative = database
+            .exif_vec
+            .get(ref_key)
+            .is_some_and(|reference| reference.contains(negative));
+        Some(if is_negative { -degrees } else { degrees })
+    };
+    let latitude = coordinate("GPSLatitude", "GPSLatitudeRef", 'S')?;
+    let longitude = coordinate("GPSLongitude", "GPSLongitudeRef", 'W')?;
+    // Cameras without a fix often write 0/0
+    (latitude != 0.0 || longitude != 0.0).then_some((latitude, longitude))
+}
+
+/// Great-circle distance.
+fn distance_km((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_phi = (lat2 - lat1).to_radians();
+    let delta_lambda = (lon2 - lon1).to_radians();
+    let a = (delta_phi / 2.0).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
@@ -0,0 +1,262 @@
+use crate::operations::open_db::{open_album_table, open_data_table};
+use crate::public::config::SERVER_CONFIG;
+use crate::public::db::job::JOB_QUEUE;
+use crate::public::error_data::handle_error;
+use crate::public::storage::STORAGE;
+use crate::public::structure::album::CollageCover;
+use crate::public::structure::database_struct::database::definition::Database;
+use anyhow::{Context, Result};
+use arrayvec::ArrayString;
+use mini_executor::BatchTask;
+use redb::ReadableTable;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Objects that were (or, in a dry run, would be) deleted or quarantined.
+    pub key_list: Vec<String>,
+    pub reclaimed_bytes: u64,
+}
+
+/// Remove objects that no item refers to any more.
+pub struct CollectGarbageTask {
+    /// Items just deleted; their objects are checked right away.
+    removed_list: Vec<Database>,
+    /// Also sweep the whole storage for unreferenced objects.
+    sweep: bool,
+}
+
+impl CollectGarbageTask {
+    pub fn removed(removed_list: Vec<Database>) -> Self {
+        Self {
+            removed_list,
+            sweep: false,
+        }
+    }
+    pub fn sweep() -> Self {
+        Self {
+            removed_list: Vec::new(),
+            sweep: true,
+        }
+    }
+}
+
+impl BatchTask for CollectGarbageTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let dry_run = SERVER_CONFIG.gc.dry_run;
+            let result = if list.iter().any(|task| task.sweep) {
+                sweep_garbage(dry_run)
+            } else {
+                let removed_list = list.into_iter().flat_map(|task| task.removed_list);
+                collect_removed(removed_list.collect(), dry_run)
+            };
+            if let Err(e) = result {
+                handle_error(e.context("Garbage collection failed"));
+            }
+        }
+    }
+}
+
+/// Reclaim every stored object no item refers to.
+pub fn sweep_garbage(dry_run: bool) -> Result<GcReport> {
+    // Jobs are read first: one that finishes during the scan has flushed its row by then.
+    let in_flight = in_flight_hashes()?;
+    let data_table = open_data_table()?;
+    let mut referenced: HashSet<String> = HashSet::new();
+    for entry in data_table.iter()? {
+        let (_, guard) = entry?;
+        referenced.extend(guard.value().object_keys());
+    }
+    drop(data_table);
+    // Album collages live next to item derivatives
+    for entry in open_album_table().iter()? {
+        let (_, guard) = entry?;
+        if let Some(image) = guard.value().collage.and_then(|collage| collage.image) {
+            referenced.insert(CollageCover::image_key(&image));
+        }
+    }
+
+    let key_list = unreferenced_keys(&referenced, &in_flight)?;
+    Ok(reclaim(key_list, dry_run))
+}
+
+/// Reclaim the objects of deleted items, unless the hash has been indexed again since.
+fn collect_removed(removed_list: Vec<Database>, dry_run: bool) -> Result<GcReport> {
+    let in_flight = in_flight_hashes()?;
+    let data_table = open_data_table()?;
+    let mut key_list = Vec::new();
+    for database in removed_list {
+        if data_table.get(&*database.hash)?.is_some() || in_flight.contains(&database.hash) {
+            continue;
+        }
+        // Check every key, not just `object_keys`: a pending video may have a partial mp4 and a
+        // reference-only item an imported copy from before.
+        for key in [
+            database.imported_key(),
+            database.compressed_key(),
+            database.thumbnail_key(),
+        ] {
+            if !key_list.contains(&key) && STORAGE.exists(&key)? {
+                key_list.push(key);
+            }
+        }
+    }
+    Ok(reclaim(key_list, dry_run))
+}
+
+/// Stored originals and derivatives that are not in `referenced`.
+///
+/// `in_flight` must be read before the rows behind `referenced`: objects of files still being
+/// indexed have no row yet and are left alone, as are objects younger than
+/// `gc.minObjectAgeMinutes`.
+pub fn unreferenced_keys(
+    referenced: &HashSet<String>,
+    in_flight: &HashSet<ArrayString<64>>,
+) -> Result<Vec<String>> {
+    let mut candidate_list = Vec::new();
+    for prefix in ["imported/", "compressed/"] {
+        candidate_list.extend(
+            STORAGE
+                .list(prefix)
+                .context(format!("Failed to list objects under {}", prefix))?,
+        );
+    }
+    select_unreferenced(
+        candidate_list,
+        referenced,
+        in_flight,
+        |key| STORAGE.modified(key),
+        SystemTime::now() - Duration::from_secs(SERVER_CONFIG.gc.min_object_age_minutes * 60),
+    )
+}
+
+/// The keys of `candidate_list` that are unreferenced, not in flight and written before `cutoff`.
+fn select_unreferenced(
+    candidate_list: Vec<String>,
+    referenced: &HashSet<String>,
+    in_flight: &HashSet<ArrayString<64>>,
+    modified: impl Fn(&str) -> Result<SystemTime>,
+    cutoff: SystemTime,
+) -> Result<Vec<String>> {
+    let mut key_list = Vec::new();
+    for key in candidate_list {
+        let is_in_flight = key_hash(&key).is_some_and(|hash| in_flight.contains(hash));
+        if referenced.contains(&key) || is_in_flight {
+            continue;
+        }
+        if modified(&key)? < cutoff {
+            key_list.push(key);
+        }
+    }
+    Ok(key_list)
+}
+
+pub fn in_flight_hashes() -> Result<HashSet<ArrayString<64>>> {
+    Ok(JOB_QUEUE
+        .read_jobs()?
+        .into_iter()
+        .filter_map(|job| job.hash)
+        .collect())
+}
+
+/// The item hash encoded in an object key such as `compressed/ab/<hash>.jpg`.
+fn key_hash(key: &str) -> Option<&str> {
+    Path::new(key).file_stem().and_then(|stem| stem.to_str())
+}
+
+/// Delete or quarantine `key_list`, logging how many bytes were freed.
+fn reclaim(key_list: Vec<String>, dry_run: bool) -> GcReport {
+    let mut report = GcReport {
+        dry_run,
+        ..Default::default()
+    };
+    for key in key_list {
+        let size = STORAGE.size(&key).unwrap_or(0);
+        if !dry_run {
+            let result = if SERVER_CONFIG.gc.quarantine {
+                STORAGE.rename(&key, &format!("quarantine/{}", key))
+            } else {
+                STORAGE.delete(&key)
+            };
+            if let Err(e) = result {
+                handle_error(e.context(format!("Failed to reclaim {}", key)));
+                continue;
+            }
+        }
+        info!(
+            "{} {} ({} bytes)",
+            match (dry_run, SERVER_CONFIG.gc.quarantine) {
+                (true, _) => "Would reclaim",
+                (false, true) => "Quarantined",
+                (false, false) => "Deleted",
+            },
+            key,
+            size
+        );
+        report.reclaimed_bytes += size;
+        report.key_list.push(key);
+    }
+    if !report.key_list.is_empty() {
+        info!(
+            "Garbage collection {} {} bytes from {} objects",
+            if dry_run {
+                "would reclaim"
+            } else {
+                "reclaimed"
+            },
+            report.reclaimed_bytes,
+            report.key_list.len()
+        );
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const IMPORTING: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    const INDEXED: &str = "1123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    const ORPHAN: &str = "2123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    const FRESH: &str = "3123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn sweep_spares_in_progress_import() {
+        let now = SystemTime::now();
+        let hour_ago = now - Duration::from_secs(3600);
+        // The import has copied its original and written its thumbnail but has no row yet.
+        let modified: HashMap<String, SystemTime> = [
+            (format!("imported/01/{}.jpg", IMPORTING), hour_ago),
+            (format!("compressed/01/{}.jpg", IMPORTING), hour_ago),
+            (format!("imported/11/{}.jpg", INDEXED), hour_ago),
+            (format!("imported/21/{}.jpg", ORPHAN), hour_ago),
+            // Its job has just finished, but the row is not visible to this scan.
+            (format!("imported/31/{}.jpg", FRESH), now),
+        ]
+        .into_iter()
+        .collect();
+        let referenced = HashSet::from([format!("imported/11/{}.jpg", INDEXED)]);
+        let in_flight = HashSet::from([ArrayString::from(IMPORTING).unwrap()]);
+
+        let mut candidate_list: Vec<_> = modified.keys().cloned().collect();
+        candidate_list.sort();
+        let key_list = select_unreferenced(
+            candidate_list,
+            &referenced,
+            &in_flight,
+            |key| Ok(modified[key]),
+            now - Duration::from_secs(60),
+        )
+        .unwrap();
+        assert_eq!(key_list, [format!("imported/21/{}.jpg", ORPHAN)]);
+    }
+}
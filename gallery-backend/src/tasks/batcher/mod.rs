@@ -1,7 +1,16 @@
+pub mod cluster_events;
+pub mod collect_garbage;
 pub mod expire_check;
 pub mod flush_query_snapshot;
 pub mod flush_tree;
 pub mod flush_tree_snapshot;
+pub mod prune_shares;
+pub mod purge_trash;
+pub mod reconcile;
+pub mod record_share_access;
+pub mod resume_job;
 pub mod start_watcher;
+pub mod update_alias;
 pub mod update_expire;
 pub mod update_tree;
+pub mod verify_integrity;
@@ -0,0 +1,60 @@
+use crate::public::config::SERVER_CONFIG;
+use crate::public::constant::redb::SHARE_ACCESS_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::error_data::handle_error;
+use crate::public::structure::album::share_limit::remove_shares;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::Result;
+use mini_executor::BatchTask;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Remove share links that have expired or used up their views from every album, and access
+/// log entries past `share.accessLogRetentionDays`.
+pub struct PruneSharesTask;
+
+impl BatchTask for PruneSharesTask {
+    fn batch_run(_: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let result = tokio::task::spawn_blocking(|| {
+                remove_shares(|_, share| share.is_expired() || share.is_exhausted())
+            })
+            .await
+            .expect("blocking task panicked");
+            match result {
+                Ok(0) => {}
+                Ok(pruned_count) => {
+                    info!("Pruned {} expired share links", pruned_count);
+                    BATCH_COORDINATOR.execute_batch_detached(UpdateTreeTask);
+                }
+                Err(e) => {
+                    handle_error(e.context("Failed to prune share links"));
+                }
+            }
+
+            if let Err(e) = tokio::task::spawn_blocking(prune_access_log)
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e.context("Failed to prune the share access log"));
+            }
+        }
+    }
+}
+
+fn prune_access_log() -> Result<()> {
+    let retention_days = SERVER_CONFIG.share.access_log_retention_days;
+    if retention_days == 0 {
+        return Ok(());
+    }
+    let cutoff = (SystemTime::now() - Duration::from_secs(retention_days * 24 * 60 * 60))
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let write_txn = TREE.in_disk.begin_write()?;
+    write_txn
+        .open_table(SHARE_ACCESS_TABLE)?
+        .retain(|_, access| access.timestamp >= cutoff)?;
+    write_txn.commit()?;
+    Ok(())
+}
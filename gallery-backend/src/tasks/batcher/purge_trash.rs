@@ -0,0 +1,101 @@
+use crate::operations::open_db::open_data_and_album_tables;
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::public::config::SERVER_CONFIG;
+use crate::public::error_data::handle_error;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::tasks::actor::album::AlbumSelfUpdateTask;
+use crate::tasks::batcher::collect_garbage::CollectGarbageTask;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use crate::tasks::{BATCH_COORDINATOR, INDEX_COORDINATOR};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use mini_executor::BatchTask;
+use redb::ReadableTable;
+use std::collections::HashSet;
+
+/// Delete items and albums that have been in the trash longer than `trash.retentionDays`.
+pub struct PurgeTrashTask;
+
+impl BatchTask for PurgeTrashTask {
+    fn batch_run(_: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let purge_list = match tokio::task::spawn_blocking(find_expired)
+                .await
+                .expect("blocking task panicked")
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    handle_error(e.context("Failed to scan the trash"));
+                    return;
+                }
+            };
+
+            if purge_list.is_empty() {
+                return;
+            }
+            info!("Purging {} trashed items and albums", purge_list.len());
+
+            let mut affected_album_ids: HashSet<ArrayString<64>> = HashSet::new();
+            let mut removed_database_list = Vec::new();
+            for abstract_data in &purge_list {
+                match abstract_data {
+                    AbstractData::Database(database) => {
+                        affected_album_ids.extend(database.album.iter().cloned());
+                        removed_database_list.push(database.clone());
+                    }
+                    AbstractData::Album(album) => {
+                        affected_album_ids.insert(album.id);
+                    }
+                }
+            }
+
+            if let Err(e) = BATCH_COORDINATOR
+                .execute_batch_waiting(FlushTreeTask::remove(purge_list))
+                .await
+            {
+                handle_error(anyhow::Error::from(e).context("Failed to purge the trash"));
+                return;
+            }
+            BATCH_COORDINATOR
+                .execute_batch_detached(CollectGarbageTask::removed(removed_database_list));
+            if let Err(e) = BATCH_COORDINATOR
+                .execute_batch_waiting(UpdateTreeTask)
+                .await
+            {
+                handle_error(anyhow::Error::from(e).context("Failed to update tree"));
+                return;
+            }
+            for album_id in affected_album_ids {
+                INDEX_COORDINATOR.execute_detached(AlbumSelfUpdateTask::new(album_id));
+            }
+        }
+    }
+}
+
+/// Trashed items and albums whose retention has run out.
+fn find_expired() -> Result<Vec<AbstractData>> {
+    let retention_days = SERVER_CONFIG.trash.retention_days;
+    let now = get_current_timestamp_u64() as u128;
+    let retention_ms = retention_days as u128 * 24 * 60 * 60 * 1000;
+
+    let (data_table, album_table) = open_data_and_album_tables();
+    let database_iter = data_table
+        .iter()?
+        .map(|entry| entry.map(|(_, guard)| AbstractData::Database(guard.value())));
+    let album_iter = album_table
+        .iter()?
+        .map(|entry| entry.map(|(_, guard)| AbstractData::Album(guard.value())));
+
+    let mut purge_list = Vec::new();
+    for entry in database_iter.chain(album_iter) {
+        let abstract_data = entry?;
+        if let Some(trashed_at) = abstract_data.trashed_at()
+            && retention_days > 0
+            && now.saturating_sub(trashed_at) >= retention_ms
+        {
+            purge_list.push(abstract_data);
+        }
+    }
+    Ok(purge_list)
+}
@@ -0,0 +1,148 @@
+use crate::operations::open_db::open_data_table;
+use crate::public::config::PRIVATE_CONFIG;
+use crate::public::db::job::JOB_QUEUE;
+use crate::public::error_data::handle_error;
+use crate::public::tui::DASHBOARD;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::start_watcher::{
+    is_valid_media_file, is_walkable_dir, submit_to_debounce_pool,
+};
+use crate::tasks::batcher::update_alias::UpdateAliasTask;
+use anyhow::Result;
+use mini_executor::BatchTask;
+use path_clean::PathClean;
+use redb::ReadableTable;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use walkdir::WalkDir;
+
+/// Walk sync paths and index files that are new or changed since they were last seen.
+///
+/// The watcher only sees events while the server is running; this catches up on anything
+/// that happened in between. It also backs the `rescan` watch mode.
+pub struct ReconcileTask {
+    /// `None` reconciles every sync path.
+    path_opt: Option<PathBuf>,
+}
+
+impl ReconcileTask {
+    pub fn all() -> Self {
+        Self { path_opt: None }
+    }
+    pub fn path(path: PathBuf) -> Self {
+        Self {
+            path_opt: Some(path),
+        }
+    }
+}
+
+impl BatchTask for ReconcileTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let root_list: Vec<PathBuf> = if list.iter().any(|task| task.path_opt.is_none()) {
+                PRIVATE_CONFIG.sync_path.iter().cloned().collect()
+            } else {
+                let root_set: HashSet<PathBuf> =
+                    list.into_iter().filter_map(|task| task.path_opt).collect();
+                root_set.into_iter().collect()
+            };
+            DASHBOARD.start_scan();
+            if let Err(e) = tokio::task::spawn_blocking(move || reconcile_task(&root_list))
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e);
+            }
+            DASHBOARD.finish_scan();
+        }
+    }
+}
+
+fn reconcile_task(root_list: &[PathBuf]) -> Result<()> {
+    // path -> (mtime, size) of every live alias we already know
+    let mut known: HashMap<String, (u128, u64)> = HashMap::new();
+    let data_table = open_data_table()?;
+    for entry in data_table.iter()? {
+        let (_, guard) = entry?;
+        let database = guard.value();
+        for file_modify in database.alias {
+            if !file_modify.is_stale() {
+                known.insert(file_modify.file, (file_modify.modified, database.size));
+            }
+        }
+    }
+    drop(data_table);
+
+    // Files with an unfinished job are left to `ResumeJobTask`.
+    let job_paths: HashSet<String> = JOB_QUEUE
+        .read_jobs()?
+        .into_iter()
+        .map(|job| job.path)
+        .collect();
+
+    let mut queued = 0;
+    let mut seen: HashSet<String> = HashSet::new();
+    for root in root_list {
+        // An unmounted share looks exactly like a deleted folder; do not mark it all stale.
+        if !root.is_dir() {
+            warn!("Skipping reconciliation of unavailable path {:?}", root);
+            continue;
+        }
+        info!("Reconciling {:?}", root);
+        for dir_entry in WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|dir_entry| {
+                !dir_entry.file_type().is_dir() || is_walkable_dir(dir_entry.path())
+            })
+            .filter_map(|dir_entry| dir_entry.ok())
+            .filter(|dir_entry| dir_entry.file_type().is_file())
+        {
+            let path = dir_entry.into_path().clean();
+            if !is_valid_media_file(&path) {
+                continue;
+            }
+            let key = path.to_string_lossy().into_owned();
+            if job_paths.contains(&key) {
+                DASHBOARD.scan_checked(false);
+                seen.insert(key);
+                continue;
+            }
+
+            let is_unchanged = match (known.get(&key), path.metadata()) {
+                (Some(&(modified, size)), Ok(metadata)) => {
+                    let mtime = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|duration| duration.as_millis());
+                    mtime == Some(modified) && metadata.len() == size
+                }
+                _ => false,
+            };
+            DASHBOARD.scan_checked(!is_unchanged);
+            seen.insert(key);
+            if !is_unchanged {
+                queued += 1;
+                submit_to_debounce_pool(path);
+            }
+        }
+
+        // Known files that are gone, for mounts where no remove event ever arrives.
+        for file in known.keys() {
+            // Files skipped by the include/exclude rules still exist and are left alone.
+            if Path::new(file).starts_with(root)
+                && !seen.contains(file)
+                && !Path::new(file).exists()
+            {
+                BATCH_COORDINATOR
+                    .execute_batch_detached(UpdateAliasTask::removed(PathBuf::from(file)));
+            }
+        }
+    }
+
+    info!("Reconciliation finished, {} files queued", queued);
+    Ok(())
+}
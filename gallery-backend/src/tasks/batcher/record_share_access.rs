@@ -0,0 +1,61 @@
+use crate::public::constant::redb::{SHARE_ACCESS_TABLE, SHARE_STATS_TABLE, SHARE_VISITOR_TABLE};
+use crate::public::db::tree::TREE;
+use crate::public::db::tree::share_access::count_share_access;
+use crate::public::error_data::handle_error;
+use crate::public::structure::share_access::ShareAccess;
+use anyhow::{Context, Result};
+use mini_executor::BatchTask;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Append to the share access log and update the share totals; a page of thumbnails is written in
+/// one transaction.
+pub struct RecordShareAccessTask {
+    access: ShareAccess,
+}
+
+impl RecordShareAccessTask {
+    pub fn new(access: ShareAccess) -> Self {
+        Self { access }
+    }
+}
+
+impl BatchTask for RecordShareAccessTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let access_list = list.into_iter().map(|task| task.access).collect();
+            if let Err(e) = tokio::task::spawn_blocking(move || record_share_access(access_list))
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e.context("Failed to record share access"));
+            }
+        }
+    }
+}
+
+fn record_share_access(access_list: Vec<ShareAccess>) -> Result<()> {
+    // Nanoseconds plus the position in the batch keep keys unique within a share
+    let base = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let write_txn = TREE
+        .in_disk
+        .begin_write()
+        .context("Failed to begin write transaction")?;
+    {
+        let mut access_table = write_txn
+            .open_table(SHARE_ACCESS_TABLE)
+            .context("Failed to open SHARE_ACCESS_TABLE")?;
+        let mut stats_table = write_txn.open_table(SHARE_STATS_TABLE)?;
+        let mut visitor_table = write_txn.open_table(SHARE_VISITOR_TABLE)?;
+        for (index, access) in access_list.iter().enumerate() {
+            access_table.insert((access.share_id.as_str(), base + index as u128), access)?;
+            count_share_access(&mut stats_table, &mut visitor_table, access)?;
+        }
+    }
+    write_txn
+        .commit()
+        .context("Failed to commit write transaction")?;
+    Ok(())
+}
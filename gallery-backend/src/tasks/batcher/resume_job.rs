@@ -0,0 +1,135 @@
+use crate::operations::open_db::open_data_table;
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::public::config::SERVER_CONFIG;
+use crate::public::constant::runtime::INDEX_RUNTIME;
+use crate::public::db::job::JOB_QUEUE;
+use crate::public::structure::job::{Job, JobStage};
+use crate::tasks::batcher::start_watcher::is_valid_media_file;
+use crate::{
+    public::error_data::handle_error,
+    workflow::{index_for_watch, transcode_for_resume},
+};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use mini_executor::BatchTask;
+use redb::ReadableTable;
+use std::{collections::HashSet, path::PathBuf};
+use walkdir::WalkDir;
+
+pub struct ResumeJobTask {
+    on_startup: bool,
+}
+
+impl ResumeJobTask {
+    /// Resume everything left over from the previous run.
+    pub fn on_startup() -> Self {
+        Self { on_startup: true }
+    }
+    /// Retry failed jobs whose backoff has elapsed.
+    pub fn due() -> Self {
+        Self { on_startup: false }
+    }
+}
+
+impl BatchTask for ResumeJobTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let on_startup = list.iter().any(|task| task.on_startup);
+            if let Err(e) = tokio::task::spawn_blocking(move || resume_job_task(on_startup))
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e);
+            }
+        }
+    }
+}
+
+fn resume_job_task(on_startup: bool) -> Result<()> {
+    let now = get_current_timestamp_u64();
+    let max_attempts = SERVER_CONFIG.job.max_attempts;
+    let jobs = JOB_QUEUE.read_jobs()?;
+
+    let known_paths: HashSet<String> = jobs.iter().map(|job| job.path.clone()).collect();
+    let known_hashes: HashSet<ArrayString<64>> = jobs.iter().filter_map(|job| job.hash).collect();
+
+    let mut resumed = 0;
+    for job in jobs {
+        if job.attempts >= max_attempts {
+            continue;
+        }
+        // Jobs without a retry time are either running or were interrupted by a restart.
+        let is_due = match job.retry_at {
+            Some(retry_at) => retry_at <= now,
+            None => on_startup,
+        };
+        if !is_due {
+            continue;
+        }
+        if job.stage != JobStage::Video && !PathBuf::from(&job.path).is_file() {
+            warn!("Dropping job for missing file {}", job.path);
+            JOB_QUEUE.finish(&PathBuf::from(&job.path))?;
+            continue;
+        }
+        spawn_job(job);
+        resumed += 1;
+    }
+
+    if on_startup {
+        resumed += resume_pending_videos(&known_hashes)?;
+        resumed += resume_upload_folder(&known_paths);
+    }
+
+    if resumed > 0 {
+        info!("Resumed {} unfinished jobs", resumed);
+    }
+    Ok(())
+}
+
+/// Videos that were indexed but never finished transcoding.
+fn resume_pending_videos(known_hashes: &HashSet<ArrayString<64>>) -> Result<usize> {
+    let data_table = open_data_table()?;
+    let mut count = 0;
+    for entry in data_table.iter()? {
+        let (_, guard) = entry?;
+        let database = guard.value();
+        if database.pending && !known_hashes.contains(&database.hash) {
+            spawn_job(Job::new_video(
+                database.source_path_string().to_string(),
+                database.hash,
+            ));
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Uploaded files are deleted once indexed, so anything left in ./upload was never finished.
+fn resume_upload_folder(known_paths: &HashSet<String>) -> usize {
+    let mut count = 0;
+    WalkDir::new("./upload")
+        .into_iter()
+        .filter_map(|dir_entry| dir_entry.ok())
+        .filter(|dir_entry| dir_entry.file_type().is_file())
+        .map(|dir_entry| dir_entry.into_path())
+        .filter(|path| is_valid_media_file(path))
+        .filter(|path| !known_paths.contains(&*path.to_string_lossy()))
+        .for_each(|path| {
+            spawn_job(Job::new(path.to_string_lossy().into_owned(), None));
+            count += 1;
+        });
+    count
+}
+
+fn spawn_job(job: Job) {
+    INDEX_RUNTIME.spawn(async move {
+        let path = PathBuf::from(&job.path);
+        let result = match (job.stage, job.hash) {
+            (JobStage::Video, Some(hash)) => transcode_for_resume(path, hash).await,
+            _ => index_for_watch(path, job.presigned_album_id_opt).await,
+        };
+        if let Err(e) = result {
+            handle_error(e);
+        }
+    });
+}
@@ -1,12 +1,22 @@
+use crate::operations::utils::sync_filter::sync_filter_for;
 use crate::public::constant::runtime::INDEX_RUNTIME;
 use crate::public::constant::{VALID_IMAGE_EXTENSIONS, VALID_VIDEO_EXTENSIONS};
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::update_alias::UpdateAliasTask;
+use crate::tasks::looper::start_rescan_loop;
 use crate::{
-    public::config::PRIVATE_CONFIG, public::error_data::handle_error, workflow::index_for_watch,
+    public::config::{PRIVATE_CONFIG, SERVER_CONFIG, WatchMode},
+    public::error_data::handle_error,
+    workflow::index_for_watch,
 };
 use anyhow::Result;
 use log::info;
 use mini_executor::BatchTask;
-use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{
+    Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{ModifyKind, RenameMode},
+};
+use path_clean::PathClean;
 use std::{
     collections::{HashMap, HashSet},
     path::{Path, PathBuf},
@@ -21,8 +31,8 @@ use walkdir::WalkDir;
 
 static IS_WATCHING: AtomicBool = AtomicBool::new(false);
 
-static WATCHER_HANDLE: LazyLock<Mutex<Option<RecommendedWatcher>>> =
-    LazyLock::new(|| Mutex::new(None));
+static WATCHER_HANDLE: LazyLock<Mutex<Vec<Box<dyn Watcher + Send>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
 
 /// The last trigger time for each path
 static DEBOUNCE_POOL: LazyLock<Mutex<HashMap<PathBuf, Instant>>> =
@@ -46,21 +56,49 @@ fn start_watcher_task() -> Result<()> {
         return Ok(());
     }
 
-    // Build the watcher.
-    let mut watcher = new_watcher()?;
+    // Build the watchers: a single native one shared by all native paths, one poller per
+    // polled path, and none for paths that are only rescanned.
+    let mut watcher_list: Vec<Box<dyn Watcher + Send>> = Vec::new();
+    let mut native_watcher_opt: Option<RecommendedWatcher> = None;
     for path in &PRIVATE_CONFIG.sync_path {
-        watcher
-            .watch(path, RecursiveMode::Recursive)
-            .map_err(|e| anyhow::anyhow!("Failed to watch path {:?}: {}", path, e))?;
-        info!("Watching path {:?}", path);
+        match SERVER_CONFIG.sync.path_config(path).watch {
+            WatchMode::Native => {
+                let watcher = match &mut native_watcher_opt {
+                    Some(watcher) => watcher,
+                    None => native_watcher_opt.insert(new_watcher()?),
+                };
+                watch_path(watcher, path)?;
+                info!("Watching path {:?}", path);
+            }
+            WatchMode::Poll { interval_secs } => {
+                let mut watcher = new_poll_watcher(Duration::from_secs(interval_secs.max(1)))?;
+                watch_path(&mut watcher, path)?;
+                info!("Polling path {:?} every {}s", path, interval_secs);
+                watcher_list.push(Box::new(watcher));
+            }
+            WatchMode::Rescan { interval_secs } => {
+                start_rescan_loop(path.clone(), Duration::from_secs(interval_secs.max(1)));
+                info!("Rescanning path {:?} every {}s", path, interval_secs);
+            }
+        }
+    }
+    if let Some(watcher) = native_watcher_opt {
+        watcher_list.push(Box::new(watcher));
     }
 
-    // Store it globally to keep it alive.
-    *WATCHER_HANDLE.lock().unwrap() = Some(watcher);
+    // Store them globally to keep them alive.
+    *WATCHER_HANDLE.lock().unwrap() = watcher_list;
     Ok(())
 }
 
-fn is_valid_media_file(path: &Path) -> bool {
+fn watch_path(watcher: &mut impl Watcher, path: &Path) -> Result<()> {
+    watcher
+        .watch(path, RecursiveMode::Recursive)
+        .map_err(|e| anyhow::anyhow!("Failed to watch path {:?}: {}", path, e))
+}
+
+/// Supported extension and accepted by the include/exclude rules of its sync path
+pub fn is_valid_media_file(path: &Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| ext.to_lowercase())
@@ -69,10 +107,16 @@ fn is_valid_media_file(path: &Path) -> bool {
                 || VALID_VIDEO_EXTENSIONS.contains(&ext.as_str())
         })
         .unwrap_or(false)
+        && sync_filter_for(path).is_none_or(|filter| filter.allows_file(path))
+}
+
+/// Whether a directory walk should descend into `dir`
+pub fn is_walkable_dir(dir: &Path) -> bool {
+    sync_filter_for(dir).is_none_or(|filter| filter.allows_dir(dir))
 }
 
 /// Push the path into the debounce pool: if there is no later event for the same path within 1 second, trigger indexing
-fn submit_to_debounce_pool(path: PathBuf) {
+pub fn submit_to_debounce_pool(path: PathBuf) {
     let now = Instant::now();
 
     {
@@ -97,7 +141,8 @@ fn submit_to_debounce_pool(path: PathBuf) {
             }
         };
 
-        if should_run && is_valid_media_file(&path) {
+        // The file may have been moved or deleted again while waiting.
+        if should_run && is_valid_media_file(&path) && path.is_file() {
             // Really need to do indexing
             if let Err(e) = index_for_watch(path, None).await {
                 handle_error(e);
@@ -107,29 +152,36 @@ fn submit_to_debounce_pool(path: PathBuf) {
 }
 
 fn new_watcher() -> Result<RecommendedWatcher> {
-    notify::recommended_watcher(move |result: Result<Event, notify::Error>| match result {
+    notify::recommended_watcher(handle_event)
+        .map_err(|e| anyhow::anyhow!("Failed to create watcher: {}", e))
+}
+
+fn new_poll_watcher(interval: Duration) -> Result<PollWatcher> {
+    PollWatcher::new(handle_event, Config::default().with_poll_interval(interval))
+        .map_err(|e| anyhow::anyhow!("Failed to create poll watcher: {}", e))
+}
+
+fn handle_event(result: Result<Event, notify::Error>) {
+    match result {
         Ok(event) => {
             match event.kind {
-                EventKind::Create(_) => {
-                    let mut path_list: HashSet<PathBuf> = HashSet::new();
+                EventKind::Create(_) => submit_created(event.paths),
 
-                    for path in event.paths {
-                        if path.is_file() {
-                            path_list.insert(path);
-                        } else if path.is_dir() {
-                            WalkDir::new(&path)
-                                .into_iter()
-                                .filter_map(|dir_entry| dir_entry.ok())
-                                .filter(|dir_entry| dir_entry.file_type().is_file())
-                                .for_each(|dir_entry| {
-                                    path_list.insert(dir_entry.into_path());
-                                });
-                        }
-                    }
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                    let from = event.paths[0].clean();
+                    let to = event.paths[1].clean();
+                    BATCH_COORDINATOR.execute_batch_detached(UpdateAliasTask::renamed(from, to));
+                }
 
-                    for path in path_list {
-                        if is_valid_media_file(&path) {
-                            submit_to_debounce_pool(path);
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    // Only one side of the rename was seen: the path either left or entered
+                    // the watched tree.
+                    for path in event.paths {
+                        if path.exists() {
+                            submit_created(vec![path]);
+                        } else {
+                            BATCH_COORDINATOR
+                                .execute_batch_detached(UpdateAliasTask::removed(path.clean()));
                         }
                     }
                 }
@@ -150,12 +202,46 @@ fn new_watcher() -> Result<RecommendedWatcher> {
                     }
                 }
 
+                EventKind::Remove(_) => {
+                    for path in event.paths {
+                        BATCH_COORDINATOR
+                            .execute_batch_detached(UpdateAliasTask::removed(path.clean()));
+                    }
+                }
+
                 _ => { /* ignore other kinds */ }
             }
         }
         Err(err) => {
             handle_error(anyhow::anyhow!("Watch error: {:#?}", err));
         }
-    })
-    .map_err(|e| anyhow::anyhow!("Failed to create watcher: {}", e))
+    }
+}
+
+/// Queue new files, descending into directories that appeared as a whole
+fn submit_created(paths: Vec<PathBuf>) {
+    let mut path_list: HashSet<PathBuf> = HashSet::new();
+
+    for path in paths {
+        if path.is_file() {
+            path_list.insert(path);
+        } else if path.is_dir() && is_walkable_dir(&path) {
+            WalkDir::new(&path)
+                .into_iter()
+                .filter_entry(|dir_entry| {
+                    !dir_entry.file_type().is_dir() || is_walkable_dir(dir_entry.path())
+                })
+                .filter_map(|dir_entry| dir_entry.ok())
+                .filter(|dir_entry| dir_entry.file_type().is_file())
+                .for_each(|dir_entry| {
+                    path_list.insert(dir_entry.into_path());
+                });
+        }
+    }
+
+    for path in path_list {
+        if is_valid_media_file(&path) {
+            submit_to_debounce_pool(path);
+        }
+    }
 }
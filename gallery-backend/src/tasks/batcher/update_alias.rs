@@ -0,0 +1,127 @@
+use crate::operations::open_db::open_data_table;
+use crate::public::constant::runtime::INDEX_RUNTIME;
+use crate::public::structure::abstract_data::AbstractData;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::flush_tree::FlushTreeTask;
+use crate::tasks::batcher::start_watcher::{is_valid_media_file, is_walkable_dir};
+use crate::{public::error_data::handle_error, workflow::index_for_watch};
+use anyhow::Result;
+use arrayvec::ArrayString;
+use chrono::Utc;
+use mini_executor::BatchTask;
+use redb::ReadableTable;
+use std::{collections::HashSet, path::PathBuf};
+use walkdir::WalkDir;
+
+enum AliasEvent {
+    Removed(PathBuf),
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// The file at `path` now holds the content of `hash`.
+    Replaced {
+        path: PathBuf,
+        hash: ArrayString<64>,
+    },
+}
+
+/// Keep `Database::alias` in step with deletions and renames inside the sync paths.
+///
+/// Only the database is touched; the imported copy stays where it is.
+pub struct UpdateAliasTask {
+    events: Vec<AliasEvent>,
+}
+
+impl UpdateAliasTask {
+    pub fn removed(path: PathBuf) -> Self {
+        Self {
+            events: vec![AliasEvent::Removed(path)],
+        }
+    }
+    pub fn replaced(path: PathBuf, hash: ArrayString<64>) -> Self {
+        Self {
+            events: vec![AliasEvent::Replaced { path, hash }],
+        }
+    }
+    pub fn renamed(from: PathBuf, to: PathBuf) -> Self {
+        Self {
+            events: vec![AliasEvent::Renamed { from, to }],
+        }
+    }
+}
+
+impl BatchTask for UpdateAliasTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let events = list.into_iter().flat_map(|task| task.events).collect();
+            if let Err(e) = tokio::task::spawn_blocking(move || update_alias_task(events))
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e);
+            }
+        }
+    }
+}
+
+fn update_alias_task(events: Vec<AliasEvent>) -> Result<()> {
+    let now = Utc::now().timestamp_millis() as u128;
+    let mut moved_paths: HashSet<PathBuf> = HashSet::new();
+    let mut changed_list = Vec::new();
+
+    let data_table = open_data_table()?;
+    for entry in data_table.iter()? {
+        let (_, guard) = entry?;
+        let mut database = guard.value();
+        let mut changed = false;
+        for event in &events {
+            match event {
+                AliasEvent::Removed(path) => {
+                    changed |= database.mark_alias_stale(path, now);
+                }
+                AliasEvent::Replaced { path, hash } if database.hash != *hash => {
+                    changed |= database.mark_alias_stale(path, now);
+                }
+                AliasEvent::Replaced { .. } => {}
+                AliasEvent::Renamed { from, to } => {
+                    let moved = database.move_alias(from, to);
+                    changed |= !moved.is_empty();
+                    moved_paths.extend(moved);
+                }
+            }
+        }
+        if changed {
+            database.refresh_missing_source_tag();
+            changed_list.push(AbstractData::Database(database));
+        }
+    }
+
+    if !changed_list.is_empty() {
+        info!("Updated aliases of {} items", changed_list.len());
+        BATCH_COORDINATOR.execute_batch_detached(FlushTreeTask::insert(changed_list));
+    }
+
+    // Files renamed into place that we have never seen still need indexing.
+    for event in events {
+        if let AliasEvent::Renamed { to, .. } = event {
+            WalkDir::new(&to)
+                .into_iter()
+                .filter_entry(|dir_entry| {
+                    !dir_entry.file_type().is_dir() || is_walkable_dir(dir_entry.path())
+                })
+                .filter_map(|dir_entry| dir_entry.ok())
+                .filter(|dir_entry| dir_entry.file_type().is_file())
+                .map(|dir_entry| dir_entry.into_path())
+                .filter(|path| is_valid_media_file(path) && !moved_paths.contains(path))
+                .for_each(|path| {
+                    INDEX_RUNTIME.spawn(async move {
+                        if let Err(e) = index_for_watch(path, None).await {
+                            handle_error(e);
+                        }
+                    });
+                });
+        }
+    }
+    Ok(())
+}
@@ -24,6 +24,10 @@ static ALLOWED_KEYS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
         "DateTimeOriginal",
         "duration",
         "rotation",
+        "GPSLatitude",
+        "GPSLatitudeRef",
+        "GPSLongitude",
+        "GPSLongitudeRef",
     ]
     .iter()
     .cloned()
@@ -0,0 +1,331 @@
+use crate::operations::hash::blake3_hasher;
+use crate::operations::indexation::generate_compressed_video::generate_compressed_video;
+use crate::operations::indexation::video_ffprobe::video_duration;
+use crate::operations::open_db::open_data_table;
+use crate::operations::utils::timestamp::get_current_timestamp_u64;
+use crate::process::info::{regenerate_metadata_for_image, regenerate_metadata_for_video};
+use crate::public::constant::redb::DATA_TABLE;
+use crate::public::db::tree::TREE;
+use crate::public::error_data::handle_error;
+use crate::public::storage::STORAGE;
+use crate::public::structure::database_struct::database::definition::{Database, StorageMode};
+use crate::public::tui::DASHBOARD;
+use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::collect_garbage::{in_flight_hashes, unreferenced_keys};
+use crate::tasks::batcher::update_tree::UpdateTreeTask;
+use anyhow::{Context, Result};
+use arrayvec::ArrayString;
+use mini_executor::BatchTask;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use redb::ReadableTable;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io,
+    path::Path,
+    sync::{LazyLock, RwLock},
+};
+
+/// Result of the last integrity verification; `None` until one has run.
+pub static INTEGRITY_REPORT: LazyLock<RwLock<Option<IntegrityReport>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub started_time: u64,
+    /// `None` while the verification is still running.
+    pub finished_time: Option<u64>,
+    pub checked: usize,
+    /// Items whose original no longer hashes to the item hash.
+    pub hash_mismatch: Vec<ArrayString<64>>,
+    /// Items whose original object (or every source file, for reference-only items) is gone.
+    pub missing_original: Vec<ArrayString<64>>,
+    /// Derivative keys that do not exist.
+    pub missing_derivative: Vec<String>,
+    /// Derivative keys that exist but cannot be decoded.
+    pub undecodable_derivative: Vec<String>,
+    /// Stored keys that no item refers to.
+    pub orphan_objects: Vec<String>,
+    /// Items whose derivatives were rebuilt.
+    pub regenerated: Vec<ArrayString<64>>,
+}
+
+impl IntegrityReport {
+    pub fn issue_count(&self) -> usize {
+        self.hash_mismatch.len()
+            + self.missing_original.len()
+            + self.missing_derivative.len()
+            + self.undecodable_derivative.len()
+            + self.orphan_objects.len()
+    }
+}
+
+/// Re-hash originals, check that derivatives exist and decode, and look for orphaned objects.
+pub struct VerifyIntegrityTask {
+    /// Rebuild missing or broken derivatives when the original is still intact.
+    regenerate: bool,
+}
+
+impl VerifyIntegrityTask {
+    pub fn new(regenerate: bool) -> Self {
+        Self { regenerate }
+    }
+}
+
+impl BatchTask for VerifyIntegrityTask {
+    fn batch_run(list: Vec<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let regenerate = list.iter().any(|task| task.regenerate);
+            if let Err(e) = tokio::task::spawn_blocking(move || verify_integrity_task(regenerate))
+                .await
+                .expect("blocking task panicked")
+            {
+                handle_error(e.context("Integrity verification failed"));
+            }
+            DASHBOARD.finish_verify();
+        }
+    }
+}
+
+/// What was found for a single item.
+#[derive(Default)]
+struct ItemCheck {
+    hash_mismatch: bool,
+    missing_original: bool,
+    missing_derivative: Vec<String>,
+    undecodable_derivative: Vec<String>,
+    regenerated: Option<Database>,
+}
+
+fn verify_integrity_task(regenerate: bool) -> Result<()> {
+    let mut report = IntegrityReport {
+        started_time: get_current_timestamp_u64(),
+        ..Default::default()
+    };
+    *INTEGRITY_REPORT.write().unwrap() = Some(report.clone());
+
+    let in_flight = in_flight_hashes()?;
+    let data_table = open_data_table()?;
+    let mut database_list = Vec::new();
+    for entry in data_table.iter()? {
+        let (_, guard) = entry?;
+        database_list.push(guard.value());
+    }
+    drop(data_table);
+
+    let expected: HashSet<String> = database_list
+        .iter()
+        .flat_map(|database| database.object_keys())
+        .collect();
+
+    info!("Verifying integrity of {} items", database_list.len());
+    DASHBOARD.start_verify(database_list.len() as u64);
+    let check_list: Vec<(ArrayString<64>, ItemCheck)> = database_list
+        .into_par_iter()
+        .map(|database| {
+            let hash = database.hash;
+            let item_check = check_item(database, regenerate);
+            DASHBOARD.verify_checked(
+                item_check.hash_mismatch
+                    || item_check.missing_original
+                    || !item_check.missing_derivative.is_empty()
+                    || !item_check.undecodable_derivative.is_empty(),
+            );
+            (hash, item_check)
+        })
+        .collect();
+
+    let mut regenerated_list = Vec::new();
+    for (hash, item_check) in check_list {
+        report.checked += 1;
+        if item_check.hash_mismatch {
+            report.hash_mismatch.push(hash);
+        }
+        if item_check.missing_original {
+            report.missing_original.push(hash);
+        }
+        report
+            .missing_derivative
+            .extend(item_check.missing_derivative);
+        report
+            .undecodable_derivative
+            .extend(item_check.undecodable_derivative);
+        if let Some(database) = item_check.regenerated {
+            report.regenerated.push(hash);
+            regenerated_list.push(database);
+        }
+    }
+    if !regenerated_list.is_empty() {
+        write_regenerated(regenerated_list)?;
+    }
+
+    report.orphan_objects = unreferenced_keys(&expected, &in_flight)?;
+
+    report.finished_time = Some(get_current_timestamp_u64());
+    info!(
+        "Integrity verification finished: {} checked, {} issues, {} regenerated",
+        report.checked,
+        report.issue_count(),
+        report.regenerated.len()
+    );
+    *INTEGRITY_REPORT.write().unwrap() = Some(report);
+    Ok(())
+}
+
+fn check_item(mut database: Database, regenerate: bool) -> ItemCheck {
+    let mut item_check = ItemCheck::default();
+
+    match verify_original(&database) {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => item_check.hash_mismatch = true,
+        Ok(None) => item_check.missing_original = true,
+        Err(err) => {
+            handle_error(err.context(format!("Failed to verify original of {}", database.hash)));
+        }
+    }
+
+    for key in database.derivative_keys() {
+        match verify_derivative(&key) {
+            Ok(true) => {}
+            Ok(false) => item_check.missing_derivative.push(key),
+            Err(err) => {
+                warn!("Derivative {} is broken: {:#}", key, err);
+                item_check.undecodable_derivative.push(key);
+            }
+        }
+    }
+
+    let is_damaged =
+        !item_check.missing_derivative.is_empty() || !item_check.undecodable_derivative.is_empty();
+    let is_original_intact = !item_check.hash_mismatch && !item_check.missing_original;
+    if regenerate && is_damaged && is_original_intact {
+        match regenerate_derivatives(&mut database, &item_check) {
+            Ok(()) => item_check.regenerated = Some(database),
+            Err(err) => {
+                handle_error(err.context(format!(
+                    "Failed to regenerate derivatives of {}",
+                    database.hash
+                )));
+            }
+        }
+    }
+    item_check
+}
+
+/// Store the fields rebuilt from the original.
+///
+/// The rows were read before the (slow) regeneration, so merge into the current rows instead of
+/// overwriting edits made in the meantime; items deleted in the meantime stay deleted.
+fn write_regenerated(regenerated_list: Vec<Database>) -> Result<()> {
+    let write_txn = TREE.in_disk.begin_write()?;
+    {
+        let mut data_table = write_txn.open_table(DATA_TABLE)?;
+        for regenerated in regenerated_list {
+            let Some(mut database) = data_table
+                .get(&*regenerated.hash)?
+                .map(|guard| guard.value())
+            else {
+                continue;
+            };
+            database.width = regenerated.width;
+            database.height = regenerated.height;
+            database.thumbhash = regenerated.thumbhash;
+            database.phash = regenerated.phash;
+            database.exif_vec = regenerated.exif_vec;
+            data_table.insert(&*database.hash, &database)?;
+        }
+    }
+    write_txn.commit()?;
+    BATCH_COORDINATOR.execute_batch_detached(UpdateTreeTask);
+    Ok(())
+}
+
+/// `Some(matches)` after re-hashing the original, `None` when it is gone.
+fn verify_original(database: &Database) -> Result<Option<bool>> {
+    let key = database.imported_key();
+    let path = match database.storage {
+        StorageMode::Imported => match STORAGE.local_path(&key) {
+            Some(path) if path.is_file() => path,
+            Some(_) => return Ok(None),
+            None if !STORAGE.exists(&key)? => return Ok(None),
+            // Hash the stored object itself, not a cached copy or a source file next to it.
+            None => {
+                return Ok(Some(
+                    blake3_hasher(STORAGE.open(&key, None)?)? == database.hash,
+                ));
+            }
+        },
+        StorageMode::Reference => match database.original_path_opt() {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    let hash = blake3_hasher(File::open(&path).context(format!("Failed to open {:?}", path))?)?;
+    Ok(Some(hash == database.hash))
+}
+
+/// `Ok(false)` when missing; an error when present but unusable.
+fn verify_derivative(key: &str) -> Result<bool> {
+    if !STORAGE.exists(key)? {
+        return Ok(false);
+    }
+    if key.ends_with(".jpg") {
+        image::load_from_memory(&STORAGE.get(key)?).context("failed to decode JPEG")?;
+    } else {
+        with_stored_file(key, |path| {
+            let duration = video_duration(&path.to_string_lossy())
+                .map_err(|err| anyhow::anyhow!("ffprobe cannot read the video: {}", err))?;
+            if duration <= 0.0 {
+                anyhow::bail!("video has no duration");
+            }
+            Ok(())
+        })?;
+    }
+    Ok(true)
+}
+
+/// Run `check` on a local file with the stored content of `key`.
+///
+/// Remote objects are downloaded to a temporary file rather than read from `./object/cache`, so
+/// the check sees what the backend holds now.
+fn with_stored_file<T>(key: &str, check: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+    if let Some(path) = STORAGE.local_path(key) {
+        return check(&path);
+    }
+    let extension = Path::new(key)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    let path = std::env::temp_dir().join(format!(
+        "urocissa-verify-{}.{}",
+        uuid::Uuid::new_v4(),
+        extension
+    ));
+    let result = File::create(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut file| Ok(io::copy(&mut STORAGE.open(key, None)?, &mut file)?))
+        .and_then(|_| check(&path));
+    let _ = fs::remove_file(&path);
+    result
+}
+
+fn regenerate_derivatives(database: &mut Database, item_check: &ItemCheck) -> Result<()> {
+    if database.ext_type == "image" {
+        regenerate_metadata_for_image(database)?;
+    } else {
+        let compressed_key = database.compressed_key();
+        let is_video_broken = item_check
+            .missing_derivative
+            .iter()
+            .chain(&item_check.undecodable_derivative)
+            .any(|key| *key == compressed_key);
+        regenerate_metadata_for_video(database)?;
+        if is_video_broken {
+            generate_compressed_video(database)?;
+        }
+    }
+    info!("Regenerated derivatives of {}", database.hash);
+    Ok(())
+}
@@ -1,6 +1,14 @@
+use crate::public::config::SERVER_CONFIG;
 use crate::public::constant::{SNAPSHOT_MAX_LIFETIME_MS, runtime::INDEX_RUNTIME};
 use crate::tasks::BATCH_COORDINATOR;
+use crate::tasks::batcher::cluster_events::ClusterEventsTask;
+use crate::tasks::batcher::collect_garbage::CollectGarbageTask;
 use crate::tasks::batcher::expire_check::ExpireCheckTask;
+use crate::tasks::batcher::prune_shares::PruneSharesTask;
+use crate::tasks::batcher::purge_trash::PurgeTrashTask;
+use crate::tasks::batcher::reconcile::ReconcileTask;
+use crate::tasks::batcher::resume_job::ResumeJobTask;
+use std::path::PathBuf;
 use std::sync::{Arc, LazyLock};
 use tokio::sync::mpsc;
 use tokio::time::{Duration, sleep};
@@ -43,3 +51,83 @@ pub async fn reset_expire_check_timer() {
         let _ = sender.send(());
     }
 }
+
+/// Wake the job queue once `delay_ms` has elapsed so that jobs whose backoff ran out are retried
+pub fn schedule_job_retry(delay_ms: u64) {
+    INDEX_RUNTIME.spawn(async move {
+        sleep(Duration::from_millis(delay_ms)).await;
+        BATCH_COORDINATOR.execute_batch_detached(ResumeJobTask::due());
+    });
+}
+
+/// Periodically reconcile a sync path that is configured for rescanning instead of watching
+pub fn start_rescan_loop(path: PathBuf, interval: Duration) {
+    INDEX_RUNTIME.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick fires immediately; the startup reconciliation already covers it.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            BATCH_COORDINATOR.execute_batch_detached(ReconcileTask::path(path.clone()));
+        }
+    });
+}
+
+/// Periodically sweep the object storage for files no item refers to
+pub fn start_gc_loop() {
+    let interval_hours = SERVER_CONFIG.gc.interval_hours;
+    if interval_hours == 0 {
+        return;
+    }
+    INDEX_RUNTIME.spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_hours * 60 * 60));
+        // Skip the immediate first tick so startup indexing can register its jobs first.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            BATCH_COORDINATOR.execute_batch_detached(CollectGarbageTask::sweep());
+        }
+    });
+}
+
+/// Hourly purge of items and albums whose trash retention has run out
+pub fn start_trash_purge_loop() {
+    if SERVER_CONFIG.trash.retention_days == 0 {
+        return;
+    }
+    INDEX_RUNTIME.spawn(async {
+        let mut ticker = tokio::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            ticker.tick().await;
+            BATCH_COORDINATOR.execute_batch_detached(PurgeTrashTask);
+        }
+    });
+}
+
+/// Hourly removal of used-up share links and of old share access log entries
+pub fn start_share_prune_loop() {
+    INDEX_RUNTIME.spawn(async {
+        let mut ticker = tokio::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            ticker.tick().await;
+            BATCH_COORDINATOR.execute_batch_detached(PruneSharesTask);
+        }
+    });
+}
+
+/// Periodically propose event albums from the timeline
+pub fn start_event_cluster_loop() {
+    let interval_hours = SERVER_CONFIG.events.interval_hours;
+    if interval_hours == 0 {
+        return;
+    }
+    INDEX_RUNTIME.spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_hours * 60 * 60));
+        // Skip the immediate first tick so startup indexing can settle first.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            BATCH_COORDINATOR.execute_batch_detached(ClusterEventsTask);
+        }
+    });
+}
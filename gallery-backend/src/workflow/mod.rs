@@ -1,16 +1,31 @@
-use crate::tasks::{
-    INDEX_COORDINATOR,
-    actor::{
-        copy::CopyTask, deduplicate::DeduplicateTask, delete_in_update::DeleteTask, hash::HashTask,
-        index::IndexTask, open_file::OpenFileTask, video::VideoTask,
+use crate::{
+    operations::open_db::open_data_table,
+    public::{
+        db::job::JOB_QUEUE,
+        error_data::handle_error,
+        structure::job::{Job, JobStage},
+        tui::{DASHBOARD, FileType},
+    },
+    tasks::{
+        BATCH_COORDINATOR, INDEX_COORDINATOR,
+        actor::{
+            album::AlbumSelfUpdateTask, copy::CopyTask, deduplicate::DeduplicateTask,
+            delete_in_update::DeleteTask, hash::HashTask, index::IndexTask,
+            open_file::OpenFileTask, video::VideoTask,
+        },
+        batcher::flush_tree::FlushTreeTask,
+        looper::schedule_job_retry,
     },
 };
-use anyhow::{Result, bail};
+use anyhow::Result;
 use arrayvec::ArrayString;
 use dashmap::DashSet;
 use log::warn;
 use path_clean::PathClean;
-use std::{path::PathBuf, sync::LazyLock};
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
 static IN_PROGRESS: LazyLock<DashSet<ArrayString<64>>> = LazyLock::new(DashSet::new);
 
@@ -34,10 +49,54 @@ pub async fn index_for_watch(
     presigned_album_id_opt: Option<ArrayString<64>>,
 ) -> Result<()> {
     let path = path.clean();
+    JOB_QUEUE.begin(Job::new(
+        path.to_string_lossy().into_owned(),
+        presigned_album_id_opt,
+    ))?;
+    let result = index_workflow(&path, presigned_album_id_opt).await;
+    settle_job(&path, result).await
+}
+
+/// Re-runs the transcode of a video whose source file has already been consumed.
+pub async fn transcode_for_resume(path: PathBuf, hash: ArrayString<64>) -> Result<()> {
+    JOB_QUEUE.begin(Job::new_video(path.to_string_lossy().into_owned(), hash))?;
+    let result = transcode_workflow(hash).await;
+    settle_job(&path, result).await
+}
+
+/// Removes the job on success; otherwise records the failure and schedules a retry.
+async fn settle_job(path: &Path, result: Result<()>) -> Result<()> {
+    match result {
+        Ok(()) => {
+            // The job shields the new objects from garbage collection until the row is on disk.
+            BATCH_COORDINATOR
+                .execute_batch_waiting(FlushTreeTask::insert(Vec::new()))
+                .await?;
+            JOB_QUEUE.finish(path)
+        }
+        Err(err) => {
+            match JOB_QUEUE.fail(path, &err) {
+                Ok(Some(delay)) => schedule_job_retry(delay),
+                Ok(None) => {}
+                Err(job_err) => {
+                    handle_error(job_err.context("Failed to record job failure"));
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+async fn index_workflow(
+    path: &Path,
+    presigned_album_id_opt: Option<ArrayString<64>>,
+) -> Result<()> {
+    let path = path.to_path_buf();
     let file = INDEX_COORDINATOR
         .execute_waiting(OpenFileTask::new(path.clone()))
         .await??;
 
+    JOB_QUEUE.advance(&path, JobStage::Hash, None)?;
     let hash = INDEX_COORDINATOR
         .execute_waiting(HashTask::new(file))
         .await??;
@@ -53,6 +112,7 @@ pub async fn index_for_watch(
         }
     };
 
+    JOB_QUEUE.advance(&path, JobStage::Dedup, Some(hash))?;
     let database_opt = INDEX_COORDINATOR
         .execute_waiting(DeduplicateTask::new(
             path.clone(),
@@ -70,15 +130,34 @@ pub async fn index_for_watch(
         }
     };
 
+    JOB_QUEUE.advance(&path, JobStage::Copy, None)?;
     database = INDEX_COORDINATOR
         .execute_waiting(CopyTask::new(database))
         .await??;
+    JOB_QUEUE.advance(&path, JobStage::Index, None)?;
+    let album_before_index = database.album.clone();
     database = INDEX_COORDINATOR
         .execute_waiting(IndexTask::new(database))
         .await??;
 
+    // Albums added by `autoTag` rules count the new item once it has been flushed
+    let rule_album_list: Vec<_> = database
+        .album
+        .difference(&album_before_index)
+        .copied()
+        .collect();
+    if !rule_album_list.is_empty() {
+        BATCH_COORDINATOR
+            .execute_batch_waiting(FlushTreeTask::insert(Vec::new()))
+            .await?;
+        for album_id in rule_album_list {
+            INDEX_COORDINATOR.execute_detached(AlbumSelfUpdateTask::new(album_id));
+        }
+    }
+
     INDEX_COORDINATOR.execute_detached(DeleteTask::new(PathBuf::from(&path)));
     if database.ext_type == "video" {
+        JOB_QUEUE.advance(&path, JobStage::Video, None)?;
         INDEX_COORDINATOR
             .execute_waiting(VideoTask::new(database))
             .await??;
@@ -86,3 +165,28 @@ pub async fn index_for_watch(
 
     Ok(())
 }
+
+async fn transcode_workflow(hash: ArrayString<64>) -> Result<()> {
+    let _guard = match try_acquire(hash) {
+        Some(g) => g,
+        None => {
+            warn!("Processing already in progress for hash: {}", hash);
+            return Ok(());
+        }
+    };
+
+    let database_opt = open_data_table()?.get(&*hash)?.map(|guard| guard.value());
+    let database = match database_opt {
+        Some(database) if database.pending => database,
+        // Deleted or already transcoded in the meantime.
+        _ => return Ok(()),
+    };
+
+    DASHBOARD.add_task(hash, database.imported_path_string(), FileType::Video);
+    // Skip the indexing state; only the transcode is left.
+    DASHBOARD.advance_task_state(&hash);
+    INDEX_COORDINATOR
+        .execute_waiting(VideoTask::new(database))
+        .await??;
+    Ok(())
+}
@@ -42,7 +42,9 @@ export const ShareSchema = z.object({
   showMetadata: z.boolean(),
   showDownload: z.boolean(),
   showUpload: z.boolean(),
-  exp: z.number()
+  exp: z.number(),
+  maxViews: z.number().nullable(),
+  includeChildren: z.boolean()
 })
 
 export const ResolvedShareSchema = z.object({